@@ -76,7 +76,7 @@ fn part2(contents: String) -> usize {
             .intersection(&owned_numbers)
             .fold(0usize, |x, _| x + 1);
         for i in card_index + 1..card_index + winning_count + 1 {
-            match multipliers.get(i).take() {
+            match multipliers.get(i) {
                 Some(x) => multipliers[i] = x + current_multiplier,
                 None => {
                     while multipliers.len() < i {
@@ -1,225 +1,145 @@
-use itertools::Itertools;
-use std::cmp::min;
-
-use aoc2023::utils::read_input_file;
-
-#[derive(Debug, Clone)]
-struct SeedRange {
-    pub start: usize,
-    pub length: usize,
-}
+use std::env;
 
-impl SeedRange {
-    pub fn contains(&self, test: usize) -> bool {
-        test.checked_sub(self.start)
-            .map(|x| x <= self.length)
-            .unwrap_or(false)
-    }
+use itertools::Itertools;
 
-    pub fn end(&self) -> usize {
-        self.start + self.length
-    }
+use aoc2023::{
+    intervals::{Interval, IntervalSet, PiecewiseShift},
+    utils::read_input_file,
+};
 
-    pub fn partition_by(&self, ranges: &Vec<MapRange>) -> Vec<SeedRange> {
-        let mut source_ranges = Vec::new();
-        let mut dest_ranges = Vec::new();
-        for range in ranges {
-            // range: .....|-----|
-            // self : ..|-----|...
-            if self.contains(range.source_start) {
-                let range_end = min(self.end(), range.source_end());
-                let length = range_end - range.source_start;
-                source_ranges.push(SeedRange {
-                    start: range.source_start,
-                    length,
-                });
-                dest_ranges.push(SeedRange {
-                    start: range.dest_start,
-                    length,
-                });
-            }
-            // range: ..|-----|...
-            // self : .....|-----|
-            else if self.contains(range.source_end()) {
-                let length = range.source_end() - self.start;
-                source_ranges.push(SeedRange {
-                    start: self.start,
-                    length,
-                });
-                dest_ranges.push(SeedRange {
-                    start: range.dest_end() - length,
-                    length,
-                })
-            }
-            // range: ..|-----------|
-            // self : .....|-----|...
-            else if range.source_end() > self.end() && range.source_start < self.start {
-                if !dest_ranges.is_empty() {
-                    panic!("Overlapping ranges exist?")
-                }
-                let left_offset = self.start - range.source_start;
-                let new_start = range.dest_start + left_offset;
-                return vec![SeedRange {
-                    start: new_start,
-                    length: self.length,
-                }];
-            }
-        }
-        if dest_ranges.is_empty() {
-            return vec![self.clone()];
+fn main() {
+    let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--breakpoints") {
+        let almanac = Almanac::from_str(&contents);
+        for (name, _) in almanac.stages.iter() {
+            println!("{}", name);
         }
-
-        source_ranges.sort_unstable_by(|x, y| x.start.cmp(&y.start));
-        let mut left_end = self.start;
-        let mut right_end: usize;
-        for range in source_ranges.iter() {
-            right_end = range.start;
-            let gap_length = right_end - left_end;
-            if right_end - left_end != 0 {
-                dest_ranges.push(SeedRange {
-                    start: left_end,
-                    length: gap_length,
-                });
-            }
-            left_end = range.end();
+        for (source, offset) in almanac.composed().pieces() {
+            println!("{} -> {} ({:+})", source, source.shifted(*offset), offset);
         }
-
-        dest_ranges
+        return;
+    }
+    if let Some(location) =
+        env::args().find_map(|x| x.strip_prefix("--inverse=").map(str::to_owned))
+    {
+        let almanac = Almanac::from_str(&contents);
+        let location: i64 = location.parse().unwrap();
+        let seeds = almanac.seeds_for_location(location);
+        println!(
+            "seeds mapping to location {}: {}",
+            location,
+            seeds.iter().join(", ")
+        );
+        let listed = seeds.intersection(&IntervalSet::from_intervals(
+            almanac.seeds.iter().map(|&x| Interval::from_len(x, 1)),
+        ));
+        println!("of which listed as seeds: {}", listed.iter().join(", "));
+        let in_ranges = seeds.intersection(&almanac.seed_ranges());
+        println!(
+            "of which inside seed ranges: {}",
+            in_ranges.iter().join(", ")
+        );
+        return;
     }
+    let part1 = part1(contents);
+    println!("part 1: {}", part1); // 836040384
+    let contents = read_input_file(file!(), "input.txt");
+    let part2 = part2(contents);
+    println!("part 2: {}", part2); // 10834440
 }
 
-#[derive(Debug)]
-struct MapRange {
-    pub source_start: usize,
-    pub dest_start: usize,
-    pub length: usize,
+struct Almanac {
+    seeds: Vec<i64>,
+    /// maps in the order they're applied, keyed by names like `seed-to-soil`
+    stages: Vec<(String, PiecewiseShift)>,
 }
 
-impl MapRange {
-    pub fn transform(&self, input_num: usize) -> usize {
-        if let Some(diff) = input_num.checked_sub(self.source_start) {
-            if diff <= self.length {
-                return self.dest_start + diff;
-            }
-        }
-        input_num
+impl Almanac {
+    pub fn from_str(contents: &str) -> Self {
+        let mut file_iter = contents.split("\n\n");
+        let seeds = file_iter
+            .next()
+            .unwrap()
+            .split(": ")
+            .nth(1)
+            .unwrap()
+            .split(' ')
+            .map(|x| x.trim().parse().unwrap())
+            .collect();
+        let stages = file_iter
+            .filter(|x| !x.trim().is_empty())
+            .map(|section| {
+                let name = section.split(' ').next().unwrap();
+                (name.to_owned(), parse_map(section))
+            })
+            .collect();
+        Almanac { seeds, stages }
     }
 
-    pub fn contains(&self, input_num: usize) -> bool {
-        if let Some(diff) = input_num.checked_sub(self.source_start) {
-            if diff <= self.length {
-                return true;
-            }
-        }
-        false
+    /// Reads the seed list as `start length` pairs, like part 2 does.
+    pub fn seed_ranges(&self) -> IntervalSet {
+        IntervalSet::from_intervals(
+            self.seeds
+                .iter()
+                .tuples()
+                .map(|(&start, &length)| Interval::from_len(start, length)),
+        )
     }
 
-    pub fn source_end(&self) -> usize {
-        self.source_start + self.length
+    /// Every stage combined into one map from seed to location.
+    pub fn composed(&self) -> PiecewiseShift {
+        self.stages
+            .iter()
+            .fold(PiecewiseShift::new(), |composed, (_, stage)| {
+                composed.then(stage)
+            })
     }
-    pub fn dest_end(&self) -> usize {
-        self.dest_start + self.length
+
+    pub fn seeds_for_location(&self, location: i64) -> IntervalSet {
+        self.composed()
+            .preimage(&IntervalSet::from_intervals([Interval::from_len(
+                location, 1,
+            )]))
     }
 }
 
-fn main() {
-    let contents = read_input_file(file!(), "input.txt");
-    let part1 = part1(contents);
-    println!("part 1: {}", part1); // 836040384
-    let contents = read_input_file(file!(), "input.txt");
-    let part2 = part2(contents);
-    println!("part 2: {}", part2); // 10834440
+/// Reads a map section like `seed-to-soil map:` followed by `dest source length` lines.
+fn parse_map(section: &str) -> PiecewiseShift {
+    let mut map = PiecewiseShift::new();
+    for line in section.split('\n').skip(1).take_while(|x| !x.is_empty()) {
+        let (dest_start, source_start, length) = line
+            .split(' ')
+            .map(|x| x.parse::<i64>().unwrap())
+            .collect_tuple()
+            .unwrap();
+        map.add_piece(
+            Interval::from_len(source_start, length),
+            dest_start - source_start,
+        );
+    }
+    map
 }
 
 fn part1(contents: String) -> usize {
-    let mut file_iter = contents.split("\n\n");
-    let seeds_input = file_iter.next().unwrap();
-    let mut seeds: Vec<_> = seeds_input
-        .split(": ")
-        .nth(1)
-        .unwrap()
-        .split(' ')
-        .map(|x| vec![x.parse::<usize>().unwrap(); 8])
-        // .map(|x| Seed::new(x.parse().unwrap()))
-        .collect();
-
-    for i in 1..8 {
-        let section = file_iter.next().unwrap();
-        let line_reader = section.split('\n').skip(1).take_while(|x| !x.is_empty());
-        let mut ranges: Vec<MapRange> = Vec::new();
-        for line in line_reader {
-            let mut line_numbers = line.split(' ').map(|x| x.parse::<usize>().unwrap());
-            let range = MapRange {
-                dest_start: line_numbers.next().unwrap(),
-                source_start: line_numbers.next().unwrap(),
-                length: line_numbers.next().unwrap(),
-            };
-
-            ranges.push(range);
-        }
-        seeds = seeds
-            .iter_mut()
-            .map(|seed| {
-                let mut new_seed = seed.clone();
-                new_seed[i] = ranges
-                    .iter()
-                    .find(|range| range.contains(seed[i - 1]))
-                    .map(|range| range.transform(seed[i - 1]))
-                    .unwrap_or(seed[i - 1]);
-                new_seed
-            })
-            .collect();
-    }
-
-    seeds
+    let almanac = Almanac::from_str(&contents);
+    let composed = almanac.composed();
+    almanac
+        .seeds
         .iter()
-        .fold(usize::MAX, |current_min, seed| min(seed[7], current_min))
+        .map(|&seed| composed.apply(seed))
+        .min()
+        .unwrap() as usize
 }
 
 fn part2(contents: String) -> usize {
-    let mut file_iter = contents.split("\n\n");
-    let seeds_input = file_iter.next().unwrap();
-    let mut seeds: Vec<_> = seeds_input
-        .split(": ")
-        .nth(1)
-        .unwrap()
-        .split(' ')
-        .chunks(2)
-        .into_iter()
-        .map(|mut chunks| SeedRange {
-            start: chunks.next().unwrap().parse().unwrap(),
-            length: chunks.next().unwrap().parse().unwrap(),
-        })
-        .collect();
-    for _iter in 1..8 {
-        let section = file_iter.next().unwrap();
-        let line_reader = section.split('\n').skip(1).take_while(|x| !x.is_empty());
-        let mut ranges: Vec<MapRange> = Vec::new();
-        for line in line_reader {
-            let mut line_numbers = line.split(' ').map(|x| x.parse::<usize>().unwrap());
-            let range = MapRange {
-                dest_start: line_numbers.next().unwrap(),
-                source_start: line_numbers.next().unwrap(),
-                length: line_numbers.next().unwrap(),
-            };
-
-            ranges.push(range);
-        }
-
-        let mut new_seeds = Vec::with_capacity(seeds.len());
-        for seed in seeds {
-            new_seeds.extend(seed.partition_by(&ranges))
-        }
-        seeds = new_seeds;
-    }
-    seeds.iter().fold(usize::MAX, |current_min, seed_range| {
-        min(current_min, seed_range.start)
-    })
+    let almanac = Almanac::from_str(&contents);
+    let locations = almanac.composed().apply_set(&almanac.seed_ranges());
+    locations.min().unwrap() as usize
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use crate::{part1, part2, read_input_file, Almanac};
 
     const P1SAMPLE01_ANSWER: usize = 35;
     const P2SAMPLE01_ANSWER: usize = 46;
@@ -236,4 +156,37 @@ mod tests {
         let res = part2(contents);
         assert_eq!(res, P2SAMPLE01_ANSWER);
     }
+
+    #[test]
+    fn composed_map_matches_stages() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let almanac = Almanac::from_str(&contents);
+        assert_eq!(almanac.stages.len(), 7);
+        assert_eq!(almanac.stages[0].0, "seed-to-soil");
+        assert_eq!(almanac.stages[6].0, "humidity-to-location");
+        let composed = almanac.composed();
+        for seed in 0..120 {
+            let location = almanac
+                .stages
+                .iter()
+                .fold(seed, |value, (_, stage)| stage.apply(value));
+            assert_eq!(composed.apply(seed), location);
+        }
+    }
+
+    #[test]
+    fn seeds_for_location() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let almanac = Almanac::from_str(&contents);
+        assert!(almanac.seeds_for_location(35).contains(13));
+        assert!(almanac.seeds_for_location(46).contains(82));
+        assert_eq!(almanac.seeds_for_location(46).len(), 1);
+    }
+
+    #[test]
+    fn arbitrary_stage_count() {
+        let contents = "seeds: 1 5 20\n\nseed-to-location map:\n100 0 10\n";
+        assert_eq!(part1(contents.to_string()), 20);
+        assert_eq!(part2(contents.to_string()), 101);
+    }
 }
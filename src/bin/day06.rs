@@ -9,32 +9,37 @@ fn main() {
     println!("part 2: {}", part2)
 }
 
-fn get_winning_ways(time: usize, distance: usize) -> usize {
-    println!("{time}, {distance}");
-    let determinant: f64 = (time * time - 4 * distance) as f64;
-    let upper_bound = ((time as f64 + determinant.sqrt()) / 2_f64).floor() as usize;
-    let lower_bound = ((time as f64 - determinant.sqrt()) / 2_f64).ceil() as usize;
-    let mut winning_ways = upper_bound - lower_bound + 1;
+// y = (n - x) * x
+// -y = (x - n) * x
+// x^2 - nx + y = 0
+// x = (n +- sqrt(n^2 - 4y))/2
+fn beats_record(time: u128, distance: u128, hold_time: u128) -> bool {
+    hold_time * (time - hold_time) > distance
+}
 
-    // check if bounds are exclusive
-    let upper_bound_result = upper_bound * (time - upper_bound);
-    let lower_bound_result = lower_bound * (time - lower_bound);
-    if upper_bound_result <= distance {
-        winning_ways -= 1;
+/// Smallest hold time that travels further than `distance`, if there is one.
+fn first_winning_hold(time: usize, distance: usize) -> Option<usize> {
+    let (time, distance) = (time as u128, distance as u128);
+    if !beats_record(time, distance, time / 2) {
+        return None;
     }
-    if lower_bound_result <= distance {
-        winning_ways -= 1;
+    // the root of x^2 - nx + y = 0 gets us within one of the answer, then walk to it exactly
+    let determinant = time * time - 4 * distance;
+    let mut hold_time = (time - determinant.isqrt()) / 2;
+    while !beats_record(time, distance, hold_time) {
+        hold_time += 1;
     }
+    while hold_time > 0 && beats_record(time, distance, hold_time - 1) {
+        hold_time -= 1;
+    }
+    Some(hold_time as usize)
+}
 
-    println!("bounds: {:?}, {:?}", lower_bound, upper_bound);
-    println!("{:?}", winning_ways);
-    winning_ways
+fn get_winning_ways(time: usize, distance: usize) -> usize {
+    // distance travelled is symmetric around time / 2
+    first_winning_hold(time, distance).map_or(0, |lower_bound| time - 2 * lower_bound + 1)
 }
 
-// y = (n - x) * x
-// -y = (x - n) * x
-// x^2 - nx + y = 0
-// x = (n +- sqrt(n^2 - 4y))/2
 fn part1(contents: String) -> usize {
     let mut file_lines = contents.split('\n').take_while(|x| !x.is_empty());
     let times = file_lines
@@ -72,7 +77,7 @@ fn part2(contents: String) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use crate::{first_winning_hold, get_winning_ways, part1, part2, read_input_file};
 
     const P1SAMPLE01_ANSWER: usize = 288;
     const P2SAMPLE01_ANSWER: usize = 71503;
@@ -89,4 +94,38 @@ mod tests {
         let res = part2(contents);
         assert_eq!(res, P2SAMPLE01_ANSWER);
     }
+
+    #[test]
+    fn matches_brute_force() {
+        for time in 0..60 {
+            for distance in 0..time * time / 4 + 3 {
+                let expected = (0..=time).filter(|x| x * (time - x) > distance).count();
+                assert_eq!(
+                    get_winning_ways(time, distance),
+                    expected,
+                    "{time}, {distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tied_record() {
+        // holding for 10 or 20 ms exactly ties the record, which doesn't count
+        assert_eq!(get_winning_ways(30, 200), 9);
+        assert_eq!(get_winning_ways(4, 4), 0);
+        assert_eq!(get_winning_ways(4, 3), 1);
+    }
+
+    #[test]
+    fn huge_race() {
+        // t^2 is far past what an f64 can hold exactly
+        let time = 4_000_000_001;
+        let hold = time / 7;
+        let distance = hold * (time - hold);
+        assert_eq!(first_winning_hold(time, distance), Some(hold + 1));
+        assert_eq!(first_winning_hold(time, distance - 1), Some(hold));
+        assert_eq!(get_winning_ways(time, distance), time - 2 * (hold + 1) + 1);
+        assert_eq!(get_winning_ways(time, time / 2 * (time - time / 2)), 0);
+    }
 }
@@ -1,10 +1,27 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, env, fs};
 
 use aoc2023::utils::read_input_file;
 use itertools::Itertools;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    let explain = env::args().any(|x| x == "--explain");
+    let csv_path = env::args().find_map(|x| x.strip_prefix("--csv=").map(str::to_owned));
+    if explain || csv_path.is_some() {
+        let rules = if env::args().any(|x| x == "--part2") {
+            Rules::jokers()
+        } else {
+            Rules::standard()
+        };
+        let ranked = rank_hands(&contents, &rules);
+        if explain {
+            print!("{}", explain_table(&ranked));
+        }
+        if let Some(path) = csv_path {
+            fs::write(path, to_csv(&ranked)).unwrap();
+        }
+        return;
+    }
     let part1 = part1(contents); // 241344943
     println!("part 1: {}", part1);
     let contents = read_input_file(file!(), "input.txt");
@@ -12,192 +29,259 @@ fn main() {
     println!("part 2: {}", part2)
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 enum HandType {
-    FiveKind,
-    FourKind,
-    FullHouse,
-    ThreeKind,
-    TwoPair,
-    OnePair,
     HighCard,
+    OnePair,
+    TwoPair,
+    ThreeKind,
+    FullHouse,
+    FourKind,
+    FiveKind,
 }
 
-static CARD_ORDER: [char; 13] = [
-    '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
-];
-
-fn get_hand_type(hand: &str) -> HandType {
-    let card_counts = hand.chars().counts();
-    let mut most_freq_count = 0;
-    let mut most_freq_char = 'H';
-    let mut second_most_freq_count = 0;
-    let mut _second_most_freq_char = 'H';
-    for (card, count) in card_counts {
-        if count > most_freq_count {
-            second_most_freq_count = most_freq_count;
-            _second_most_freq_char = most_freq_char;
-            most_freq_count = count;
-            most_freq_char = card;
-        } else if count > second_most_freq_count {
-            _second_most_freq_char = card;
-            second_most_freq_count = count;
-        }
-    }
-    match most_freq_count {
-        5 => HandType::FiveKind,
-        4 => HandType::FourKind,
-        3 => {
-            if second_most_freq_count == 2 {
-                HandType::FullHouse
-            } else {
-                HandType::ThreeKind
+impl HandType {
+    /// Classifies a hand by the sizes of its two largest groups of matching cards.
+    fn from_group_sizes(most_freq_count: usize, second_most_freq_count: usize) -> Self {
+        match most_freq_count {
+            5.. => HandType::FiveKind,
+            4 => HandType::FourKind,
+            3 => {
+                if second_most_freq_count >= 2 {
+                    HandType::FullHouse
+                } else {
+                    HandType::ThreeKind
+                }
             }
-        }
-        2 => {
-            if second_most_freq_count == 2 {
-                HandType::TwoPair
-            } else {
-                HandType::OnePair
+            2 => {
+                if second_most_freq_count == 2 {
+                    HandType::TwoPair
+                } else {
+                    HandType::OnePair
+                }
             }
+            _ => HandType::HighCard,
         }
-        _ => HandType::HighCard,
     }
 }
 
-fn part1(contents: String) -> usize {
-    let mut hands_bids: Vec<(&str, usize)> = contents
-        .split('\n')
-        .take_while(|x| !x.is_empty())
-        .map(|x| {
-            let mut parts = x.split(' ');
-            (
-                parts.next().unwrap(),
-                parts.next().map(|x| x.parse::<usize>().unwrap()).unwrap(),
-            )
-        })
-        .collect();
+/// How a variant of Camel Cards ranks hands.
+#[derive(Debug, Clone)]
+struct Rules {
+    /// every card, weakest first
+    card_order: Vec<char>,
+    /// cards that act as whatever makes the hand strongest
+    wild_cards: Vec<char>,
+    hand_size: usize,
+}
 
-    hands_bids.sort_unstable_by(|(l, _), (r, _)| {
-        let left_hand_type = get_hand_type(l);
-        let right_hand_type = get_hand_type(r);
-        if left_hand_type > right_hand_type {
-            Ordering::Less
-        } else if left_hand_type == right_hand_type {
-            for (left_card, right_card) in l.chars().zip(r.chars()) {
-                let left_lead_value = CARD_ORDER.iter().position(|x| *x == left_card).unwrap();
-                let right_lead_value = CARD_ORDER.iter().position(|x| *x == right_card).unwrap();
-                let compare_result = left_lead_value.cmp(&right_lead_value);
-                if compare_result != Ordering::Equal {
-                    return compare_result;
-                }
-            }
-            Ordering::Equal
-        } else {
-            Ordering::Greater
+impl Rules {
+    pub fn standard() -> Self {
+        Rules {
+            card_order: "23456789TJQKA".chars().collect(),
+            wild_cards: Vec::new(),
+            hand_size: 5,
         }
-    });
+    }
 
-    println!("{:?}", hands_bids);
-    hands_bids
-        .iter()
-        .enumerate()
-        .fold(0, |total, (rank, (_, bid))| total + bid * (rank + 1))
-}
-static PART_2_CARD_ORDER: [char; 13] = [
-    'J', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'Q', 'K', 'A',
-];
-
-fn part_2_get_hand_type(hand: &str) -> HandType {
-    let card_counts = hand.chars().counts();
-    let joker_count = *card_counts.get(&'J').unwrap_or(&0usize);
-    let mut most_freq_count = 0;
-    let mut most_freq_char = 'H';
-    let mut second_most_freq_count = 0;
-    let mut _second_most_freq_char = 'H';
-    for (card, count) in card_counts {
-        if card == 'J' {
-            continue;
-        }
-        if count > most_freq_count {
-            second_most_freq_count = most_freq_count;
-            _second_most_freq_char = most_freq_char;
-            most_freq_count = count;
-            most_freq_char = card;
-        } else if count > second_most_freq_count {
-            _second_most_freq_char = card;
-            second_most_freq_count = count;
+    /// Part 2's rules, where `J` is the weakest card but is wild.
+    pub fn jokers() -> Self {
+        Rules {
+            card_order: "J23456789TQKA".chars().collect(),
+            wild_cards: vec!['J'],
+            hand_size: 5,
         }
     }
-    match most_freq_count + joker_count {
-        5 => HandType::FiveKind,
-        4 => HandType::FourKind,
-        3 => {
-            if second_most_freq_count == 2 {
-                HandType::FullHouse
-            } else {
-                HandType::ThreeKind
-            }
+
+    fn card_value(&self, card: char) -> usize {
+        self.card_order
+            .iter()
+            .position(|&x| x == card)
+            .unwrap_or_else(|| panic!("unknown card {}", card))
+    }
+
+    fn hand_type(&self, cards: &str) -> HandType {
+        let card_counts = cards.chars().counts();
+        let wild_count: usize = self
+            .wild_cards
+            .iter()
+            .filter_map(|card| card_counts.get(card))
+            .sum();
+        let mut group_sizes: Vec<usize> = card_counts
+            .iter()
+            .filter(|(card, _)| !self.wild_cards.contains(card))
+            .map(|(_, &count)| count)
+            .collect();
+        group_sizes.sort_unstable_by(|a, b| b.cmp(a));
+        // wild cards always do best joining the biggest group
+        let most_freq_count = group_sizes.first().copied().unwrap_or(0) + wild_count;
+        let second_most_freq_count = group_sizes.get(1).copied().unwrap_or(0);
+        HandType::from_group_sizes(most_freq_count, second_most_freq_count)
+    }
+
+    /// The card the wild cards in `cards` end up standing in for, if there are any. Ties
+    /// between equally big groups go to the stronger card.
+    fn wild_substitute(&self, cards: &str) -> Option<char> {
+        if !cards.chars().any(|x| self.wild_cards.contains(&x)) {
+            return None;
         }
-        2 => {
-            if second_most_freq_count == 2 {
-                HandType::TwoPair
-            } else {
-                HandType::OnePair
-            }
+        let card_counts = cards.chars().counts();
+        card_counts
+            .iter()
+            .filter(|(card, _)| !self.wild_cards.contains(card))
+            .max_by_key(|(&card, &count)| (count, self.card_value(card)))
+            .map(|(&card, _)| card)
+            .or_else(|| {
+                // all wild, so become the strongest card there is
+                self.card_order
+                    .iter()
+                    .rev()
+                    .find(|x| !self.wild_cards.contains(x))
+                    .copied()
+            })
+    }
+}
+
+/// A hand scored under some `Rules`. Hands order by type first, then card by card.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hand {
+    hand_type: HandType,
+    card_values: Vec<usize>,
+    cards: String,
+    wild_substitute: Option<char>,
+}
+
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hand_type
+            .cmp(&other.hand_type)
+            .then_with(|| self.card_values.cmp(&other.card_values))
+            // only matters for rules that give two cards the same value, and keeps the order
+            // consistent with `Eq` there; the substitute follows from the cards
+            .then_with(|| self.cards.cmp(&other.cards))
+    }
+}
+
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hand {
+    pub fn new(cards: &str, rules: &Rules) -> Self {
+        assert_eq!(
+            cards.chars().count(),
+            rules.hand_size,
+            "hand {} has the wrong number of cards",
+            cards
+        );
+        Hand {
+            hand_type: rules.hand_type(cards),
+            card_values: cards.chars().map(|x| rules.card_value(x)).collect(),
+            cards: cards.to_owned(),
+            wild_substitute: rules.wild_substitute(cards),
         }
-        _ => HandType::HighCard,
     }
 }
-fn part2(contents: String) -> usize {
-    let mut hands_bids: Vec<(&str, usize)> = contents
+
+/// Parses the hands and bids in `contents`, sorted from weakest to strongest so that each
+/// hand's rank is its index plus one.
+fn rank_hands(contents: &str, rules: &Rules) -> Vec<(Hand, usize)> {
+    let mut hands_bids: Vec<(Hand, usize)> = contents
         .split('\n')
         .take_while(|x| !x.is_empty())
         .map(|x| {
-            let mut parts = x.split(' ');
-            (
-                parts.next().unwrap(),
-                parts.next().map(|x| x.parse::<usize>().unwrap()).unwrap(),
-            )
+            let (cards, bid) = x.split_once(' ').unwrap();
+            (Hand::new(cards, rules), bid.parse::<usize>().unwrap())
         })
         .collect();
+    hands_bids.sort_unstable();
+    hands_bids
+}
 
-    hands_bids.sort_unstable_by(|(l, _), (r, _)| {
-        let left_hand_type = part_2_get_hand_type(l);
-        let right_hand_type = part_2_get_hand_type(r);
-        if left_hand_type > right_hand_type {
-            Ordering::Less
-        } else if left_hand_type < right_hand_type {
-            Ordering::Greater
-        } else {
-            for (left_card, right_card) in l.chars().zip(r.chars()) {
-                let left_lead_value = PART_2_CARD_ORDER
-                    .iter()
-                    .position(|x| *x == left_card)
-                    .unwrap();
-                let right_lead_value = PART_2_CARD_ORDER
-                    .iter()
-                    .position(|x| *x == right_card)
-                    .unwrap();
-                let compare_result = left_lead_value.cmp(&right_lead_value);
-                if compare_result != Ordering::Equal {
-                    return compare_result;
-                }
-            }
-            Ordering::Equal
-        }
-    });
+/// The columns shared by `explain_table` and `to_csv`, one row per hand in rank order.
+fn table_rows(ranked: &[(Hand, usize)]) -> Vec<[String; 6]> {
+    ranked
+        .iter()
+        .enumerate()
+        .map(|(i, (hand, bid))| {
+            [
+                hand.cards.clone(),
+                format!("{:?}", hand.hand_type),
+                hand.wild_substitute
+                    .map_or(String::new(), |x| x.to_string()),
+                bid.to_string(),
+                (i + 1).to_string(),
+                (bid * (i + 1)).to_string(),
+            ]
+        })
+        .collect()
+}
 
-    println!("{:?}", hands_bids);
-    hands_bids
+const TABLE_HEADERS: [&str; 6] = ["hand", "type", "wild as", "bid", "rank", "winnings"];
+
+fn explain_table(ranked: &[(Hand, usize)]) -> String {
+    let rows = table_rows(ranked);
+    let widths: Vec<usize> = (0..TABLE_HEADERS.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .chain([TABLE_HEADERS[i].len()])
+                .max()
+                .unwrap()
+        })
+        .collect();
+    let format_row = |row: Vec<&str>| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(cell, &width)| format!("{:<width$}", cell))
+            .join(" | ")
+            .trim_end()
+            .to_owned()
+            + "\n"
+    };
+    let mut table = format_row(TABLE_HEADERS.to_vec());
+    table += &(widths.iter().map(|&width| "-".repeat(width)).join("-+-") + "\n");
+    for row in rows.iter() {
+        table += &format_row(row.iter().map(|x| x.as_str()).collect());
+    }
+    let total: usize = ranked
+        .iter()
+        .enumerate()
+        .map(|(i, (_, bid))| bid * (i + 1))
+        .sum();
+    table + &format!("total winnings: {}\n", total)
+}
+
+fn to_csv(ranked: &[(Hand, usize)]) -> String {
+    let mut csv = TABLE_HEADERS.join(",") + "\n";
+    for row in table_rows(ranked) {
+        csv += &(row.join(",") + "\n");
+    }
+    csv
+}
+
+fn total_winnings(contents: &str, rules: &Rules) -> usize {
+    rank_hands(contents, rules)
         .iter()
         .enumerate()
         .fold(0, |total, (rank, (_, bid))| total + bid * (rank + 1))
 }
 
+fn part1(contents: String) -> usize {
+    total_winnings(&contents, &Rules::standard())
+}
+
+fn part2(contents: String) -> usize {
+    total_winnings(&contents, &Rules::jokers())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use crate::{
+        explain_table, part1, part2, rank_hands, read_input_file, to_csv, Hand, HandType, Rules,
+    };
 
     const P1SAMPLE01_ANSWER: usize = 6440;
     const P2SAMPLE01_ANSWER: usize = 5905;
@@ -214,4 +298,71 @@ mod tests {
         let res = part2(contents);
         assert_eq!(res, P2SAMPLE01_ANSWER);
     }
+
+    #[test]
+    fn hand_ordering() {
+        let rules = Rules::standard();
+        assert!(Hand::new("33332", &rules) > Hand::new("2AAAA", &rules));
+        assert!(Hand::new("77888", &rules) > Hand::new("77788", &rules));
+        assert!(Hand::new("KK677", &rules) > Hand::new("KTJJT", &rules));
+        assert_eq!(Hand::new("KTJJT", &rules).hand_type, HandType::TwoPair);
+
+        let rules = Rules::jokers();
+        assert_eq!(Hand::new("KTJJT", &rules).hand_type, HandType::FourKind);
+        assert_eq!(Hand::new("JJJJJ", &rules).hand_type, HandType::FiveKind);
+        assert!(Hand::new("QQQQ2", &rules) > Hand::new("JKKK2", &rules));
+    }
+
+    #[test]
+    fn custom_rules() {
+        let rules = Rules {
+            card_order: "abcd".chars().collect(),
+            wild_cards: vec!['a', 'b'],
+            hand_size: 3,
+        };
+        assert_eq!(Hand::new("abc", &rules).hand_type, HandType::ThreeKind);
+        assert_eq!(Hand::new("cdc", &rules).hand_type, HandType::OnePair);
+        assert!(Hand::new("dcd", &rules) > Hand::new("cdc", &rules));
+    }
+
+    #[test]
+    fn wild_substitute_gives_same_type() {
+        let rules = Rules::jokers();
+        let contents = read_input_file(file!(), "sample.txt");
+        let sample_hands = contents
+            .split('\n')
+            .take_while(|x| !x.is_empty())
+            .map(|x| &x[..5]);
+        let more_hands = [
+            "JJJJJ", "JJJJ2", "J2345", "2J3J4", "AAJKK", "QJJQ2", "JKKK2", "2233J", "23456",
+            "J9J9J",
+        ];
+        for cards in sample_hands.chain(more_hands) {
+            let hand = Hand::new(cards, &rules);
+            let Some(substitute) = hand.wild_substitute else {
+                assert!(!hand.cards.contains('J'));
+                continue;
+            };
+            let substituted = hand.cards.replace('J', &substitute.to_string());
+            assert_eq!(
+                Rules::standard().hand_type(&substituted),
+                hand.hand_type,
+                "{}",
+                hand.cards
+            );
+        }
+        assert_eq!(Hand::new("JJJJJ", &rules).wild_substitute, Some('A'));
+        assert_eq!(Hand::new("KTJJT", &rules).wild_substitute, Some('T'));
+    }
+
+    #[test]
+    fn explain_sample() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let ranked = rank_hands(&contents, &Rules::jokers());
+        let table = explain_table(&ranked);
+        assert!(table.contains("KTJJT | FourKind | T       | 220 | 5    | 1100\n"));
+        assert!(table.ends_with("total winnings: 5905\n"));
+        let csv = to_csv(&ranked);
+        assert!(csv.starts_with("hand,type,wild as,bid,rank,winnings\n32T3K,OnePair,,765,1,765\n"));
+    }
 }
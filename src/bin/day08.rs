@@ -0,0 +1,394 @@
+use std::{collections::HashMap, time::Instant};
+
+use aoc2023::utils::read_input_file;
+
+fn main() {
+    let contents = read_input_file(file!(), "input.txt");
+    let start = Instant::now();
+    let part1 = part1(contents.clone());
+    let duration = start.elapsed();
+    println!("part 1: {}", part1);
+    println!("part 1 took {:?}", duration);
+    let start2 = Instant::now();
+    let paths = Network::from_str(&contents).ghost_paths();
+    let part2 = first_common_end(&paths).expect("ghosts are never all on Z at once");
+    let duration2 = start2.elapsed();
+    let problems = lcm_shortcut_problems(&paths);
+    if !problems.is_empty() {
+        eprintln!("the LCM shortcut doesn't work for this input:");
+        for problem in problems.iter() {
+            eprintln!("  {}", problem);
+        }
+        if let Some(shortcut) = lcm_shortcut(&paths) {
+            eprintln!("  it would have given {}", shortcut);
+        }
+    }
+    println!("part 2: {}", part2);
+    println!("part 2 took {:?}", duration2);
+}
+
+struct Network {
+    /// `true` for every `L` in the instructions
+    instructions: Vec<bool>,
+    names: Vec<String>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+}
+
+impl Network {
+    pub fn from_str(contents: &str) -> Self {
+        let (instruction_line, node_lines) = contents
+            .split_once("\n\n")
+            .expect("the instructions should be followed by a blank line and the nodes");
+        let instructions = instruction_line.trim().chars().map(|x| x == 'L').collect();
+        let lines: Vec<&str> = node_lines
+            .split('\n')
+            .take_while(|x| !x.is_empty())
+            .collect();
+        let names: Vec<String> = lines
+            .iter()
+            .map(|x| x.split(" = ").next().unwrap().to_owned())
+            .collect();
+        let indices: HashMap<&str, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let mut left = Vec::with_capacity(lines.len());
+        let mut right = Vec::with_capacity(lines.len());
+        for line in lines {
+            let destinations = line
+                .find('(')
+                .map(|x| &line[x + 1..line.len() - 1])
+                .unwrap_or_else(|| panic!("{} should look like AAA = (BBB, CCC)", line));
+            let (left_name, right_name) = destinations
+                .split_once(", ")
+                .unwrap_or_else(|| panic!("{} should look like AAA = (BBB, CCC)", line));
+            let index = |name: &str| {
+                *indices
+                    .get(name)
+                    .unwrap_or_else(|| panic!("{} leads to {}, which isn't a node", line, name))
+            };
+            left.push(index(left_name));
+            right.push(index(right_name));
+        }
+        Network {
+            instructions,
+            names,
+            left,
+            right,
+        }
+    }
+
+    fn index_of(&self, name: &str) -> usize {
+        self.names
+            .iter()
+            .position(|x| x == name)
+            .unwrap_or_else(|| panic!("there's no node named {}", name))
+    }
+
+    fn step(&self, node: usize, time: usize) -> usize {
+        if self.instructions[time % self.instructions.len()] {
+            self.left[node]
+        } else {
+            self.right[node]
+        }
+    }
+
+    /// Walks from `start` until it starts repeating itself, noting every time it lands on a
+    /// node `is_end` accepts.
+    fn trace(&self, start: usize, is_end: impl Fn(&str) -> bool) -> GhostPath {
+        // a walker is back where it's been once it's on the same node at the same point in
+        // the instructions
+        let mut seen: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut end_times = Vec::new();
+        let mut node = start;
+        let mut time = 0;
+        loop {
+            let state = (node, time % self.instructions.len());
+            if let Some(&cycle_start) = seen.get(&state) {
+                return GhostPath {
+                    start: self.names[start].clone(),
+                    cycle_start,
+                    cycle_length: time - cycle_start,
+                    end_times,
+                };
+            }
+            seen.insert(state, time);
+            if is_end(&self.names[node]) {
+                end_times.push(time);
+            }
+            node = self.step(node, time);
+            time += 1;
+        }
+    }
+
+    pub fn ghost_paths(&self) -> Vec<GhostPath> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.ends_with('A'))
+            .map(|(i, _)| self.trace(i, |name| name.ends_with('Z')))
+            .collect()
+    }
+}
+
+/// Where a walker ends up over time: it wanders for `cycle_start` steps, then repeats the
+/// same `cycle_length` steps forever.
+#[derive(Debug)]
+struct GhostPath {
+    start: String,
+    cycle_start: usize,
+    cycle_length: usize,
+    /// every time before the first repeat that the walker is on an end node
+    end_times: Vec<usize>,
+}
+
+impl GhostPath {
+    fn is_end_at(&self, time: usize) -> bool {
+        let time = if time < self.cycle_start {
+            time
+        } else {
+            self.cycle_start + (time - self.cycle_start) % self.cycle_length
+        };
+        self.end_times.binary_search(&time).is_ok()
+    }
+
+    /// Remainders mod `cycle_length` of the times the walker is on an end once it's looping.
+    fn cycle_residues(&self) -> Vec<usize> {
+        self.end_times
+            .iter()
+            .filter(|&&x| x >= self.cycle_start)
+            .map(|x| x % self.cycle_length)
+            .collect()
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+/// Solves `x = a1 (mod m1)` and `x = a2 (mod m2)` together, giving `x mod lcm(m1, m2)`.
+fn combine_residues((a1, m1): (i128, i128), (a2, m2): (i128, i128)) -> Option<(i128, i128)> {
+    // extended euclid: g = m1 * p + m2 * q
+    let (mut old_r, mut r) = (m1, m2);
+    let (mut old_p, mut p) = (1, 0);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_p, p) = (p, old_p - quotient * p);
+    }
+    let g = old_r;
+    if (a2 - a1) % g != 0 {
+        return None;
+    }
+    let modulus = m1 / g * m2;
+    let k = ((a2 - a1) / g * old_p).rem_euclid(m2 / g);
+    Some(((a1 + m1 * k).rem_euclid(modulus), modulus))
+}
+
+/// The first time every walker is on an end node at once, if that ever happens.
+fn first_common_end(paths: &[GhostPath]) -> Option<usize> {
+    let settled_time = paths.iter().map(|x| x.cycle_start).max()?;
+    // until everyone is looping there is no pattern to lean on, so just check each time
+    for time in 0..settled_time {
+        if paths.iter().all(|path| path.is_end_at(time)) {
+            return Some(time);
+        }
+    }
+
+    let mut candidates: Vec<(i128, i128)> = vec![(0, 1)];
+    for path in paths {
+        let residues = path.cycle_residues();
+        candidates = candidates
+            .iter()
+            .flat_map(|&candidate| {
+                residues.iter().filter_map(move |&residue| {
+                    combine_residues(candidate, (residue as i128, path.cycle_length as i128))
+                })
+            })
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+    }
+    candidates
+        .iter()
+        .map(|&(residue, modulus)| {
+            let settled_time = settled_time as i128;
+            if residue >= settled_time {
+                residue
+            } else {
+                residue + (settled_time - residue + modulus - 1) / modulus * modulus
+            }
+        })
+        .min()
+        .map(|x| x as usize)
+}
+
+/// Reasons the usual "LCM of the first end times" answer would be wrong for these walkers.
+/// That shortcut only works if each walker is on an end exactly at the multiples of the first
+/// time it gets to one.
+fn lcm_shortcut_problems(paths: &[GhostPath]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for path in paths {
+        let Some(&first_end) = path.end_times.iter().find(|&&x| x > 0) else {
+            problems.push(format!("{} never reaches an end", path.start));
+            continue;
+        };
+        // past one full loop everything repeats, so this is every time that matters
+        let mismatch = (1..path.cycle_start + path.cycle_length)
+            .find(|&time| path.is_end_at(time) != (time % first_end == 0));
+        if let Some(time) = mismatch {
+            if time % first_end == 0 {
+                problems.push(format!(
+                    "{} isn't on an end at {}, a multiple of its first end time {}",
+                    path.start, time, first_end
+                ));
+            } else {
+                problems.push(format!(
+                    "{} is on an end at {}, which isn't a multiple of its first end time {}",
+                    path.start, time, first_end
+                ));
+            }
+        } else if path.cycle_length % first_end != 0 {
+            problems.push(format!(
+                "{} loops every {} steps, which isn't a multiple of its first end time {}",
+                path.start, path.cycle_length, first_end
+            ));
+        }
+    }
+    problems
+}
+
+/// The answer the LCM shortcut gives, right or not.
+fn lcm_shortcut(paths: &[GhostPath]) -> Option<usize> {
+    paths
+        .iter()
+        .map(|path| path.end_times.iter().find(|&&x| x > 0).copied())
+        .try_fold(1, |total, first_end| Some(lcm(total, first_end?)))
+}
+
+fn part1(contents: String) -> usize {
+    let network = Network::from_str(&contents);
+    let path = network.trace(network.index_of("AAA"), |name| name == "ZZZ");
+    *path.end_times.first().expect("ZZZ is never reached")
+}
+
+/// Just the answer, for the sample tests. `main` keeps the ghost paths to check the LCM
+/// shortcut against as well.
+#[cfg(test)]
+fn part2(contents: String) -> usize {
+    let network = Network::from_str(&contents);
+    first_common_end(&network.ghost_paths()).expect("ghosts are never all on Z at once")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use crate::{
+        first_common_end, lcm_shortcut, lcm_shortcut_problems, part1, part2, read_input_file,
+        Network,
+    };
+    struct Sample {
+        pub input_file: &'static str,
+        pub part_num: u8,
+        pub expected_out: usize,
+    }
+    impl Sample {
+        pub fn run(&self) {
+            let contents = read_input_file(file!(), self.input_file);
+            let start = Instant::now();
+            let res = if self.part_num == 1 {
+                part1(contents)
+            } else {
+                part2(contents)
+            };
+            let duration = start.elapsed();
+            println!("test took {:?}", duration);
+            assert_eq!(res, self.expected_out);
+        }
+    }
+
+    #[test]
+    fn first_sample() {
+        Sample {
+            input_file: "sample.txt",
+            part_num: 1,
+            expected_out: 2,
+        }
+        .run()
+    }
+
+    #[test]
+    fn repeated_instructions() {
+        Sample {
+            input_file: "sample2.txt",
+            part_num: 1,
+            expected_out: 6,
+        }
+        .run()
+    }
+
+    #[test]
+    fn ghost_sample_part_two() {
+        Sample {
+            input_file: "sample3.txt",
+            part_num: 2,
+            expected_out: 6,
+        }
+        .run()
+    }
+
+    #[test]
+    fn ghost_sample_fits_lcm_shortcut() {
+        let contents = read_input_file(file!(), "sample3.txt");
+        let paths = Network::from_str(&contents).ghost_paths();
+        assert!(lcm_shortcut_problems(&paths).is_empty());
+    }
+
+    #[test]
+    fn offset_cycles() {
+        // 1A is on 1Z at every even time and 2A is on 2Z from time 3 on, so they first meet
+        // at 4 rather than lcm(2, 3)
+        let contents = "L\n\n1A = (1B, 1B)\n1B = (1Z, 1Z)\n1Z = (1B, 1B)\n\
+                        2A = (2B, 2B)\n2B = (2C, 2C)\n2C = (2Z, 2Z)\n2Z = (2Z, 2Z)\n";
+        let paths = Network::from_str(contents).ghost_paths();
+        assert_eq!(first_common_end(&paths), Some(4));
+        assert_eq!(lcm_shortcut(&paths), Some(6));
+        let problems = lcm_shortcut_problems(&paths);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("2A"));
+    }
+
+    #[test]
+    fn never_meet() {
+        // 1A is on an end at even times and 2A at odd times
+        let contents = "L\n\n1A = (1B, 1B)\n1B = (1Z, 1Z)\n1Z = (1B, 1B)\n\
+                        2A = (2Z, 2Z)\n2Z = (2B, 2B)\n2B = (2Z, 2Z)\n";
+        let paths = Network::from_str(contents).ghost_paths();
+        assert_eq!(first_common_end(&paths), None);
+    }
+
+    #[test]
+    fn end_before_loop() {
+        // 1A only passes 1Z once on its way into a loop, 2A needs 3 steps to line up
+        let contents = "L\n\n1A = (1B, 1B)\n1B = (1Z, 1Z)\n1Z = (1C, 1C)\n1C = (1Z, 1Z)\n\
+                        2A = (2B, 2B)\n2B = (2Z, 2Z)\n2Z = (2Z, 2Z)\n";
+        let paths = Network::from_str(contents).ghost_paths();
+        assert_eq!(first_common_end(&paths), Some(2));
+        let contents = "L\n\n1A = (1Z, 1Z)\n1Z = (1B, 1B)\n1B = (1B, 1B)\n\
+                        2A = (2B, 2B)\n2B = (2Z, 2Z)\n2Z = (2Z, 2Z)\n";
+        let paths = Network::from_str(contents).ghost_paths();
+        assert_eq!(first_common_end(&paths), None);
+        assert!(!lcm_shortcut_problems(&paths).is_empty());
+    }
+}
@@ -1,9 +1,32 @@
-#![feature(iter_map_windows)]
+use std::env;
 
-use aoc2023::utils::read_input_file;
+use aoc2023::{
+    sequence::{Polynomial, Rational},
+    utils::read_input_file,
+};
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--degrees") {
+        for (values, polynomial) in fit_sequences(&contents) {
+            let degree = polynomial
+                .degree()
+                .map_or("zero".to_owned(), |x| x.to_string());
+            println!("degree {:>4}: {:?}", degree, values);
+        }
+        return;
+    }
+    if let Some(offset) = env::args().find_map(|x| x.strip_prefix("--at=").map(str::to_owned)) {
+        // offsets count from the first value, so -1 is part 2's and the length is part 1's
+        let offset: Rational = offset.parse().unwrap();
+        for (values, polynomial) in fit_sequences(&contents) {
+            match polynomial.at_rational(offset) {
+                Ok(value) => println!("{}: {:?}", value, values),
+                Err(e) => println!("{}: {:?}", e, values),
+            }
+        }
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1);
     let contents = read_input_file(file!(), "input.txt");
@@ -11,55 +34,39 @@ fn main() {
     println!("part 2: {}", part2)
 }
 
-fn part1(contents: String) -> isize {
-    let mut res = 0;
-
-    for line in contents.split('\n').take_while(|x| !x.is_empty()) {
-        let mut current_seq: Vec<isize> = line
-            .split(' ')
-            .map(|x| x.parse::<isize>().unwrap())
-            .collect();
-
-        let mut last_numbers: Vec<isize> = Vec::with_capacity(current_seq.len());
-        last_numbers.push(*current_seq.last().unwrap());
-        while !current_seq.iter().all(|x| *x == 0) {
-            current_seq = current_seq.iter().map_windows(|[x, y]| *y - *x).collect();
-            last_numbers.push(*current_seq.last().unwrap());
-        }
-        let next_value = last_numbers.iter().sum::<isize>();
-        res += next_value;
-    }
-    res
+/// Each line's values along with the polynomial through them.
+fn fit_sequences(contents: &str) -> Vec<(Vec<i64>, Polynomial)> {
+    contents
+        .split('\n')
+        .take_while(|x| !x.is_empty())
+        .map(|line| {
+            let values: Vec<i64> = line.split(' ').map(|x| x.parse().unwrap()).collect();
+            let polynomial = Polynomial::fit(&values).expect("sequence overflowed while fitting");
+            (values, polynomial)
+        })
+        .collect()
 }
 
-fn part2(contents: String) -> isize {
-    let mut res = 0;
-
-    for line in contents.split('\n').take_while(|x| !x.is_empty()) {
-        let mut current_seq: Vec<isize> = line
-            .split(' ')
-            .map(|x| x.parse::<isize>().unwrap())
-            .collect();
+fn part1(contents: String) -> i64 {
+    fit_sequences(&contents)
+        .iter()
+        .map(|(values, polynomial)| polynomial.at(values.len() as i64).unwrap())
+        .sum()
+}
 
-        let mut first_numbers: Vec<isize> = Vec::with_capacity(current_seq.len());
-        first_numbers.push(*current_seq.first().unwrap());
-        while !current_seq.iter().all(|x| *x == 0) {
-            current_seq = current_seq.iter().map_windows(|[x, y]| *y - *x).collect();
-            first_numbers.push(*current_seq.first().unwrap());
-        }
-        let next_value = first_numbers.iter().rev().fold(0, |acc, new| new - acc);
-        dbg!(&next_value);
-        res += next_value;
-    }
-    res
+fn part2(contents: String) -> i64 {
+    fit_sequences(&contents)
+        .iter()
+        .map(|(_, polynomial)| polynomial.at(-1).unwrap())
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use crate::{fit_sequences, part1, part2, read_input_file};
 
-    const P1SAMPLE01_ANSWER: isize = 114;
-    const P2SAMPLE01_ANSWER: isize = 2;
+    const P1SAMPLE01_ANSWER: i64 = 114;
+    const P2SAMPLE01_ANSWER: i64 = 2;
 
     #[test]
     fn p1sample01() {
@@ -73,4 +80,16 @@ mod tests {
         let res = part2(contents);
         assert_eq!(res, P2SAMPLE01_ANSWER);
     }
+
+    #[test]
+    fn far_offsets() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let fitted = fit_sequences(&contents);
+        let degrees: Vec<_> = fitted.iter().map(|(_, x)| x.degree()).collect();
+        assert_eq!(degrees, [Some(1), Some(2), Some(3)]);
+        // 1 3 6 10 15 21 are the triangular numbers (x + 1)(x + 2) / 2
+        let triangular = &fitted[1].1;
+        assert_eq!(triangular.at(999), Ok(500500));
+        assert_eq!(triangular.at(-1_000_001), Ok(499_999_500_000));
+    }
 }
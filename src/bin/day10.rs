@@ -1,10 +1,27 @@
-use std::collections::HashSet;
+use colored::{ColoredString, Colorize};
+use std::{
+    collections::HashSet,
+    env,
+    fmt::{self, Display},
+};
 
-use aoc2023::utils::read_input_file;
+use aoc2023::utils::{pretty_print, read_input_file};
 use array2d::Array2D;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--render") {
+        let lines = contents.split('\n').take_while(|x| !x.is_empty()).collect();
+        let mut map = PipeMap::new(lines);
+        if let Err(e) = map.find_loop() {
+            println!("{}", e);
+            return;
+        }
+        map.rewrite_start_pos();
+        pretty_print(&map.render());
+        println!("enclosed tiles: {}", map.get_enclosed_count());
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1); // 6875, actually first try, even on the tests wtf i love rust
     let contents = read_input_file(file!(), "input.txt");
@@ -36,9 +53,31 @@ impl Coord {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum LoopError {
+    NoStart,
+    NoLoop { start: Coord, connections: usize },
+}
+
+impl Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoopError::NoStart => write!(f, "there is no S on the map"),
+            LoopError::NoLoop { start, connections } => write!(
+                f,
+                "S at ({}, {}) isn't on a closed loop ({} of its neighbours connect to it)",
+                start.x, start.y, connections
+            ),
+        }
+    }
+}
+
 struct PipeMap {
     pub raw_map: Array2D<char>,
     pipe_coords: HashSet<Coord>,
+    start: Option<Coord>,
+    /// the tiles the loop leaves the start through
+    start_neighbours: Option<(Coord, Coord)>,
 }
 impl PipeMap {
     pub fn new(lines: Vec<&str>) -> PipeMap {
@@ -48,108 +87,119 @@ impl PipeMap {
         PipeMap {
             raw_map: Array2D::from_iter_row_major(flat_iter, height, width).unwrap(),
             pipe_coords: HashSet::new(),
+            start: None,
+            start_neighbours: None,
         }
     }
 
-    fn get_starting_position(&self) -> Coord {
+    fn get_starting_position(&self) -> Option<Coord> {
         for (y, mut row) in self.raw_map.rows_iter().enumerate() {
             if let Some(x) = row.position(|x| *x == 'S') {
-                return Coord { x, y };
+                return Some(Coord { x, y });
             }
         }
-        unreachable!()
+        None
     }
 
     fn get(&self, pos: &Coord) -> Option<char> {
         self.raw_map.get(pos.y, pos.x).copied()
     }
 
+    /// The two tiles the pipe at `pos` connects, or `None` if it isn't a pipe or one of its
+    /// ends points off the map.
     fn get_next_positions(&self, pos: &Coord) -> Option<(Coord, Coord)> {
-        let current_pipe = self.get(pos).unwrap();
-        match current_pipe {
-            '|' => Some((pos.north().unwrap(), pos.south().unwrap())),
-            '-' => Some((pos.west().unwrap(), pos.east().unwrap())),
-            '7' => Some((pos.west().unwrap(), pos.south().unwrap())),
-            'L' => Some((pos.east().unwrap(), pos.north().unwrap())),
-            'J' => Some((pos.west().unwrap(), pos.north().unwrap())),
-            'F' => Some((pos.east().unwrap(), pos.south().unwrap())),
-            'S' => {
-                let neighbors = vec![pos.west(), pos.east(), pos.north(), pos.south()];
-                let mut res: Vec<Coord> = Vec::with_capacity(2);
-                for test_coord in neighbors.into_iter().flatten() {
-                    if let Some((first, second)) = self.get_next_positions(&test_coord) {
-                        if first == *pos || second == *pos {
-                            res.push(test_coord)
-                        }
-                    }
-                }
-                assert_eq!(res.len(), 2);
-                Some((res[0], res[1]))
-            }
-            _ => None,
-        }
+        let (first, second) = match self.get(pos)? {
+            '|' => (pos.north(), pos.south()),
+            '-' => (pos.west(), pos.east()),
+            '7' => (pos.west(), pos.south()),
+            'L' => (pos.east(), pos.north()),
+            'J' => (pos.west(), pos.north()),
+            'F' => (pos.east(), pos.south()),
+            _ => return None,
+        };
+        let on_map = |x: Option<Coord>| x.filter(|x| self.get(x).is_some());
+        Some((on_map(first)?, on_map(second)?))
     }
 
+    /// Where a walk through `target` coming from `previous` goes next, if `target` is a pipe
+    /// that actually connects back to `previous`.
     fn get_next_position(&self, target: &Coord, previous: &Coord) -> Option<Coord> {
-        let possible_positions = self.get_next_positions(target);
-        possible_positions.map(|(first, second)| if first == *previous { second } else { first })
-    }
-
-    pub fn get_farthest_distance_on_loop(&mut self) -> isize {
-        let starting_position = self.get_starting_position();
-        self.pipe_coords.insert(starting_position);
-        let next_positions = self.get_next_positions(&starting_position);
-        let (mut left_head, mut right_head) =
-            next_positions.expect("Starting position should be connected to pipes");
-        self.pipe_coords.insert(left_head);
-        self.pipe_coords.insert(right_head);
-        let mut slow_left_head = starting_position;
-        let mut slow_right_head = starting_position;
-        let mut move_counter = 1;
+        let (first, second) = self.get_next_positions(target)?;
+        if first == *previous {
+            Some(second)
+        } else if second == *previous {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    /// Follows the pipes out of `start` through `first`, returning every tile on the way if
+    /// they lead back to `start`.
+    fn trace_loop(&self, start: &Coord, first: Coord) -> Option<Vec<Coord>> {
+        let mut path = vec![*start, first];
+        let mut previous = *start;
+        let mut current = first;
         loop {
-            move_counter += 1;
-            let mut new_position = self
-                .get_next_position(&left_head, &slow_left_head)
-                .expect("left head should always be on pipes");
-            self.pipe_coords.insert(new_position);
-            if new_position == right_head {
-                return move_counter;
-            } else {
-                slow_left_head = left_head;
-                left_head = new_position;
-            }
-            new_position = self
-                .get_next_position(&right_head, &slow_right_head)
-                .expect("heads should always be on pipes");
-            self.pipe_coords.insert(new_position);
-            if new_position == left_head {
-                return move_counter;
-            } else {
-                slow_right_head = right_head;
-                right_head = new_position;
+            // every step has to connect both ways, so the walk can only run into a dead end
+            // or come back to the start, never go round a loop that doesn't include it
+            let next = self.get_next_position(&current, &previous)?;
+            if next == *start {
+                return Some(path);
             }
+            path.push(next);
+            previous = current;
+            current = next;
         }
     }
 
+    /// Finds the loop through `S` and remembers which tiles are on it. When more than two
+    /// neighbours of `S` connect to it, the first one that leads back round to `S` is used.
+    pub fn find_loop(&mut self) -> Result<usize, LoopError> {
+        let start = self.get_starting_position().ok_or(LoopError::NoStart)?;
+        let neighbours = [start.west(), start.east(), start.north(), start.south()];
+        let Some(path) = neighbours
+            .iter()
+            .flatten()
+            .find_map(|&x| self.trace_loop(&start, x))
+        else {
+            let connections = neighbours
+                .iter()
+                .flatten()
+                .filter(|x| self.get_next_position(x, &start).is_some())
+                .count();
+            return Err(LoopError::NoLoop { start, connections });
+        };
+        self.start = Some(start);
+        self.start_neighbours = Some((path[1], *path.last().unwrap()));
+        let loop_length = path.len();
+        self.pipe_coords = path.into_iter().collect();
+        Ok(loop_length)
+    }
+
+    pub fn get_farthest_distance_on_loop(&mut self) -> Result<isize, LoopError> {
+        Ok(self.find_loop()? as isize / 2)
+    }
+
     pub fn rewrite_start_pos(&mut self) {
-        let start_pos = self.get_starting_position();
-        let neighbors = self.get_next_positions(&start_pos).unwrap();
-        let new_pipe_char = if Some(neighbors.0) == start_pos.west()
-            && Some(neighbors.1) == start_pos.east()
-        {
-            '-'
-        } else if Some(neighbors.0) == start_pos.west() && Some(neighbors.1) == start_pos.north() {
-            'J'
-        } else if Some(neighbors.0) == start_pos.west() && Some(neighbors.1) == start_pos.south() {
-            '7'
-        } else if Some(neighbors.0) == start_pos.east() && Some(neighbors.1) == start_pos.north() {
-            'L'
-        } else if Some(neighbors.0) == start_pos.east() && Some(neighbors.1) == start_pos.south() {
-            'F'
-        } else if Some(neighbors.0) == start_pos.north() && Some(neighbors.1) == start_pos.south() {
-            '|'
-        } else {
-            panic!("Starting point neighbors don't correspond to valid pipe!")
+        let start_pos = self
+            .start
+            .expect("the loop should be found before rewriting the start");
+        let (first, last) = self.start_neighbours.unwrap();
+        let connects = |side: Option<Coord>| side == Some(first) || side == Some(last);
+        let new_pipe_char = match (
+            connects(start_pos.north()),
+            connects(start_pos.east()),
+            connects(start_pos.south()),
+            connects(start_pos.west()),
+        ) {
+            (true, false, true, false) => '|',
+            (false, true, false, true) => '-',
+            (true, true, false, false) => 'L',
+            (true, false, false, true) => 'J',
+            (false, false, true, true) => '7',
+            (false, true, true, false) => 'F',
+            _ => unreachable!("the loop leaves the start through two different sides"),
         };
 
         self.raw_map
@@ -201,6 +251,35 @@ impl PipeMap {
         num_borders_seen & 1 == 1
     }
 
+    /// Draws the map with box-drawing pipes: the loop in bold with the start highlighted,
+    /// everything else dimmed, and tiles off the loop coloured by whether
+    /// `check_if_enclosed` counts them as inside (green) or outside (blue).
+    ///
+    /// Expects the loop to be traced and the start rewritten already.
+    pub fn render(&self) -> Array2D<ColoredString> {
+        let mut rendered = Array2D::filled_with(
+            " ".normal(),
+            self.raw_map.num_rows(),
+            self.raw_map.num_columns(),
+        );
+        for (y, row) in self.raw_map.rows_iter().enumerate() {
+            for (x, &tile) in row.enumerate() {
+                let pos = Coord { x, y };
+                let glyph = box_glyph(tile).to_string();
+                rendered[(y, x)] = if Some(pos) == self.start {
+                    glyph.bold().yellow().on_red()
+                } else if self.pipe_coords.contains(&pos) {
+                    glyph.bold()
+                } else if self.check_if_enclosed(&pos) {
+                    glyph.green().dimmed()
+                } else {
+                    glyph.blue().dimmed()
+                };
+            }
+        }
+        rendered
+    }
+
     pub fn get_enclosed_count(&self) -> isize {
         let mut res = 0;
         let width = self.raw_map.num_columns();
@@ -216,23 +295,38 @@ impl PipeMap {
     }
 }
 
+fn box_glyph(pipe: char) -> char {
+    match pipe {
+        '|' => '│',
+        '-' => '─',
+        'L' => '└',
+        'J' => '┘',
+        '7' => '┐',
+        'F' => '┌',
+        other => other,
+    }
+}
+
 fn part1(contents: String) -> isize {
     let lines = contents.split('\n').take_while(|x| !x.is_empty()).collect();
     let mut map = PipeMap::new(lines);
     map.get_farthest_distance_on_loop()
+        .unwrap_or_else(|e| panic!("{}", e))
 }
 
 fn part2(contents: String) -> isize {
     let lines = contents.split('\n').take_while(|x| !x.is_empty()).collect();
     let mut map = PipeMap::new(lines);
-    map.get_farthest_distance_on_loop();
+    map.find_loop().unwrap_or_else(|e| panic!("{}", e));
     map.rewrite_start_pos();
     map.get_enclosed_count()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use colored::Color;
+
+    use crate::{part1, part2, read_input_file, Coord, LoopError, PipeMap};
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
@@ -309,4 +403,66 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn render_matches_enclosed_count() {
+        let contents = read_input_file(file!(), "sample5.txt");
+        let lines = contents.split('\n').take_while(|x| !x.is_empty()).collect();
+        let mut map = PipeMap::new(lines);
+        map.find_loop().unwrap();
+        map.rewrite_start_pos();
+        let start = map.start.unwrap();
+        let rendered = map.render();
+
+        let first_row: String = rendered.row_iter(0).unwrap().map(|x| &**x).collect();
+        assert_eq!(first_row, ".┌────┐┌┐┌┐┌┐┌─┐....");
+        assert_eq!(&*rendered[(start.y, start.x)], "┌");
+        assert_eq!(rendered[(start.y, start.x)].bgcolor, Some(Color::Red));
+        let inside = rendered
+            .elements_row_major_iter()
+            .filter(|x| x.fgcolor == Some(Color::Green))
+            .count();
+        assert_eq!(inside as isize, map.get_enclosed_count());
+    }
+
+    #[test]
+    fn start_with_dead_end_neighbour() {
+        // the pipe west of S runs off the map, so only the loop through north and south closes
+        let contents = ".....\n.F-7.\n-S.|.\n.L-J.\n";
+        assert_eq!(part1(contents.to_string()), 4);
+        assert_eq!(part2(contents.to_string()), 1);
+        let mut map = PipeMap::new(contents.split('\n').take_while(|x| !x.is_empty()).collect());
+        map.find_loop().unwrap();
+        map.rewrite_start_pos();
+        assert_eq!(map.raw_map[(2, 1)], '|');
+    }
+
+    #[test]
+    fn start_with_four_neighbours() {
+        let contents = "|F7\nFSJ\nLJ.\n";
+        assert_eq!(part1(contents.to_string()), 2);
+        assert_eq!(part2(contents.to_string()), 0);
+    }
+
+    #[test]
+    fn no_loop() {
+        let mut map = PipeMap::new(vec![".F7", ".S|", "..."]);
+        assert_eq!(
+            map.find_loop(),
+            Err(LoopError::NoLoop {
+                start: Coord { x: 1, y: 1 },
+                connections: 1
+            })
+        );
+        let mut map = PipeMap::new(vec!["F-7", "|.|", "L-J"]);
+        assert_eq!(map.find_loop(), Err(LoopError::NoStart));
+        assert_eq!(
+            LoopError::NoLoop {
+                start: Coord { x: 1, y: 1 },
+                connections: 1
+            }
+            .to_string(),
+            "S at (1, 1) isn't on a closed loop (1 of its neighbours connect to it)"
+        );
+    }
 }
@@ -1,7 +1,26 @@
+use std::{collections::BTreeSet, env};
+
 use aoc2023::utils::read_input_file;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if let Some(factor) = env::args().find_map(|x| x.strip_prefix("--factor=").map(str::to_owned)) {
+        let factor: usize = factor.parse().unwrap();
+        let map = GalaxyMap::from_str(&contents);
+        println!("distance sum: {}", map.distance_sum(factor));
+        for (label, pair) in [
+            ("nearest", map.nearest_pair(factor)),
+            ("farthest", map.farthest_pair(factor)),
+        ] {
+            if let Some(pair) = pair {
+                println!(
+                    "{}: ({}, {}) and ({}, {}), {} apart",
+                    label, pair.first.x, pair.first.y, pair.second.x, pair.second.y, pair.distance
+                );
+            }
+        }
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1); // 9565386
     let contents = read_input_file(file!(), "input.txt");
@@ -21,114 +40,160 @@ impl Coord {
     }
 }
 
-fn get_blank_col_indices(map: Vec<&str>) -> Vec<usize> {
-    let mut res = vec![true; map[0].len()];
-    for line in map {
-        for (x, char) in line.chars().enumerate() {
-            if char == '#' {
-                res[x] = false;
-            }
-        }
-    }
-
-    res.iter()
-        .enumerate()
-        .filter(|(_, is_empty)| **is_empty)
-        .map(|(index, _)| index)
-        .collect()
+/// Two galaxies, by where they are on the original image, and how far apart they are once
+/// the image has expanded.
+#[derive(PartialEq, Debug, Clone, Copy, Eq)]
+struct Pair {
+    first: Coord,
+    second: Coord,
+    distance: usize,
 }
 
-fn part1(contents: String) -> isize {
-    let lines: Vec<_> = contents.split('\n').take_while(|x| !x.is_empty()).collect();
-    let mut stars: Vec<Coord> = Vec::new();
+struct GalaxyMap {
+    /// positions before expansion, in reading order
+    galaxies: Vec<Coord>,
+    blank_rows: Vec<usize>,
+    blank_cols: Vec<usize>,
+}
 
-    for (y, line) in lines.iter().enumerate() {
-        for (x, char) in line.chars().enumerate() {
-            if char == '#' {
-                stars.push(Coord { x, y });
-            }
+impl GalaxyMap {
+    pub fn from_str(contents: &str) -> Self {
+        let lines: Vec<&str> = contents.split('\n').take_while(|x| !x.is_empty()).collect();
+        let galaxies: Vec<Coord> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.char_indices()
+                    .filter(|(_, char)| *char == '#')
+                    .map(move |(x, _)| Coord { x, y })
+            })
+            .collect();
+        let mut occupied_rows = vec![false; lines.len()];
+        let mut occupied_cols = vec![false; lines.first().map_or(0, |x| x.len())];
+        for galaxy in galaxies.iter() {
+            occupied_rows[galaxy.y] = true;
+            occupied_cols[galaxy.x] = true;
+        }
+        let blank = |occupied: &[bool]| (0..occupied.len()).filter(|&i| !occupied[i]).collect();
+        let blank_rows = blank(&occupied_rows);
+        let blank_cols = blank(&occupied_cols);
+        GalaxyMap {
+            galaxies,
+            blank_rows,
+            blank_cols,
         }
     }
 
-    let blank_line_indices: Vec<usize> = lines
-        .iter()
-        .enumerate()
-        .filter(|(_, line)| line.chars().all(|x| x == '.'))
-        .map(|(index, _)| index)
-        .collect();
-    let blank_col_indices: Vec<usize> = get_blank_col_indices(lines);
-
-    for star in stars.iter_mut() {
-        let num_prev_expanded_lines = blank_line_indices
-            .iter()
-            .take_while(|y| **y < star.y)
-            .count();
-        let num_prev_expanded_cols = blank_col_indices
+    /// Where each galaxy ends up when every blank row and column becomes `factor` of them.
+    /// A factor of 0 removes the blank lines altogether.
+    fn expanded(&self, factor: usize) -> Vec<Coord> {
+        // the blank lists are sorted, so the number before a galaxy is a binary search away.
+        // there can't be more blank lines before a galaxy than its coordinate, so taking them
+        // off first never underflows
+        let expand = |position: usize, blank: &[usize]| {
+            let blank_before = blank.partition_point(|&x| x < position);
+            position - blank_before + blank_before * factor
+        };
+        self.galaxies
             .iter()
-            .take_while(|x| **x < star.x)
-            .count();
-        star.x += num_prev_expanded_cols;
-        star.y += num_prev_expanded_lines;
+            .map(|galaxy| Coord {
+                x: expand(galaxy.x, &self.blank_cols),
+                y: expand(galaxy.y, &self.blank_rows),
+            })
+            .collect()
     }
 
-    let mut res = 0;
-
-    for (index, star) in stars.iter().enumerate() {
-        for other_star in stars.iter().skip(index + 1) {
-            res += star.distance_from(*other_star)
-        }
+    /// The sum of the distances between every pair of galaxies.
+    pub fn distance_sum(&self, factor: usize) -> usize {
+        let expanded = self.expanded(factor);
+        axis_distance_sum(expanded.iter().map(|x| x.x).collect())
+            + axis_distance_sum(expanded.iter().map(|x| x.y).collect())
     }
 
-    res as isize
-}
-
-fn part2(contents: String) -> isize {
-    let lines: Vec<_> = contents.split('\n').take_while(|x| !x.is_empty()).collect();
-    let mut stars: Vec<Coord> = Vec::new();
-
-    for (y, line) in lines.iter().enumerate() {
-        for (x, char) in line.chars().enumerate() {
-            if char == '#' {
-                stars.push(Coord { x, y });
+    pub fn nearest_pair(&self, factor: usize) -> Option<Pair> {
+        let expanded = self.expanded(factor);
+        let mut order: Vec<usize> = (0..expanded.len()).collect();
+        order.sort_unstable_by_key(|&i| (expanded[i].x, expanded[i].y));
+        // sweep left to right, only keeping galaxies close enough in x to beat the best pair
+        // so far, ordered by y so the ones close enough in y can be picked out too
+        let mut best: Option<(usize, usize, usize)> = None;
+        let mut active: BTreeSet<(usize, usize)> = BTreeSet::new();
+        let mut oldest = 0;
+        for &i in order.iter() {
+            let galaxy = expanded[i];
+            let window = best.map_or(usize::MAX, |(distance, _, _)| distance);
+            while expanded[order[oldest]].x.saturating_add(window) < galaxy.x {
+                active.remove(&(expanded[order[oldest]].y, order[oldest]));
+                oldest += 1;
+            }
+            let nearby = (galaxy.y.saturating_sub(window), 0)
+                ..=(galaxy.y.saturating_add(window), usize::MAX);
+            for &(_, j) in active.range(nearby) {
+                let distance = galaxy.distance_from(expanded[j]);
+                if best.is_none_or(|(best_distance, _, _)| distance < best_distance) {
+                    best = Some((distance, j, i));
+                }
             }
+            active.insert((galaxy.y, i));
         }
+        best.map(|(distance, i, j)| self.pair(i, j, distance))
     }
 
-    let blank_line_indices: Vec<usize> = lines
-        .iter()
-        .enumerate()
-        .filter(|(_, line)| line.chars().all(|x| x == '.'))
-        .map(|(index, _)| index)
-        .collect();
-    let blank_col_indices: Vec<usize> = get_blank_col_indices(lines);
-
-    for star in stars.iter_mut() {
-        let num_prev_expanded_lines = blank_line_indices
-            .iter()
-            .take_while(|y| **y < star.y)
-            .count();
-        let num_prev_expanded_cols = blank_col_indices
+    pub fn farthest_pair(&self, factor: usize) -> Option<Pair> {
+        // rotating by 45 degrees turns manhattan distance into the larger of the differences
+        // along the two diagonals, so only the extremes along each diagonal matter
+        let expanded = self.expanded(factor);
+        if expanded.len() < 2 {
+            return None;
+        }
+        let diagonals: [fn(&Coord) -> isize; 2] = [
+            |c| c.x as isize + c.y as isize,
+            |c| c.x as isize - c.y as isize,
+        ];
+        diagonals
             .iter()
-            .take_while(|x| **x < star.x)
-            .count();
-        star.x += num_prev_expanded_cols * (1000000 - 1);
-        star.y += num_prev_expanded_lines * (1000000 - 1);
+            .filter_map(|diagonal| {
+                let lowest = (0..expanded.len()).min_by_key(|&i| diagonal(&expanded[i]))?;
+                let highest = (0..expanded.len()).max_by_key(|&i| diagonal(&expanded[i]))?;
+                let distance = expanded[lowest].distance_from(expanded[highest]);
+                Some(self.pair(lowest.min(highest), lowest.max(highest), distance))
+            })
+            .max_by_key(|pair| pair.distance)
     }
 
-    let mut res = 0;
-
-    for (index, star) in stars.iter().enumerate() {
-        for other_star in stars.iter().skip(index + 1) {
-            res += star.distance_from(*other_star)
+    fn pair(&self, first: usize, second: usize, distance: usize) -> Pair {
+        Pair {
+            first: self.galaxies[first],
+            second: self.galaxies[second],
+            distance,
         }
     }
+}
 
-    res as isize
+/// The sum of `|a - b|` over every pair of values. Once sorted, each value is bigger than
+/// everything before it, so it adds itself once for each of those and takes away their total.
+fn axis_distance_sum(mut values: Vec<usize>) -> usize {
+    values.sort_unstable();
+    let mut before = 0;
+    let mut total = 0;
+    for (i, value) in values.iter().enumerate() {
+        total += value * i - before;
+        before += value;
+    }
+    total
+}
+
+fn part1(contents: String) -> isize {
+    GalaxyMap::from_str(&contents).distance_sum(2) as isize
+}
+
+fn part2(contents: String) -> isize {
+    GalaxyMap::from_str(&contents).distance_sum(1_000_000) as isize
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use crate::{part1, part2, read_input_file, GalaxyMap};
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
@@ -155,4 +220,66 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn second_part_sample() {
+        Sample {
+            input_file: "sample.txt",
+            part_num: 2,
+            expected_out: 82000210,
+        }
+        .run()
+    }
+
+    #[test]
+    fn other_factors() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let map = GalaxyMap::from_str(&contents);
+        assert_eq!(map.distance_sum(10), 1030);
+        assert_eq!(map.distance_sum(100), 8410);
+        // a factor of 1 leaves the image as it is
+        assert_eq!(map.distance_sum(1), 292);
+        // and a factor of 0 is the same as deleting the blank rows and columns
+        let squashed: String = contents
+            .lines()
+            .filter(|line| line.contains('#'))
+            .map(|line| {
+                line.char_indices()
+                    .filter(|&(x, _)| contents.lines().any(|line| line.as_bytes()[x] == b'#'))
+                    .map(|(_, char)| char)
+                    .chain(['\n'])
+                    .collect::<String>()
+            })
+            .collect();
+        assert_eq!(
+            map.distance_sum(0),
+            GalaxyMap::from_str(&squashed).distance_sum(1)
+        );
+    }
+
+    #[test]
+    fn pairs_match_brute_force() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let map = GalaxyMap::from_str(&contents);
+        for factor in [0, 1, 2, 10, 1_000_000] {
+            let expanded = map.expanded(factor);
+            let distances: Vec<usize> = (0..expanded.len())
+                .flat_map(|i| (0..i).map(move |j| (i, j)))
+                .map(|(i, j)| expanded[i].distance_from(expanded[j]))
+                .collect();
+            assert_eq!(map.distance_sum(factor), distances.iter().sum::<usize>());
+            let nearest = map.nearest_pair(factor).unwrap();
+            assert_eq!(nearest.distance, *distances.iter().min().unwrap());
+            let farthest = map.farthest_pair(factor).unwrap();
+            assert_eq!(farthest.distance, *distances.iter().max().unwrap());
+        }
+    }
+
+    #[test]
+    fn too_few_galaxies() {
+        let map = GalaxyMap::from_str("...\n.#.\n");
+        assert_eq!(map.distance_sum(2), 0);
+        assert_eq!(map.nearest_pair(2), None);
+        assert_eq!(map.farthest_pair(2), None);
+    }
 }
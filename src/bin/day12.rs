@@ -1,6 +1,6 @@
 use std::time::Instant;
 
-use aoc2023::utils::read_input_file;
+use aoc2023::{parallel, utils::read_input_file};
 use array2d::Array2D;
 
 fn main() {
@@ -17,7 +17,7 @@ fn main() {
 
 fn ways_to_fit(line: &str, group_size: usize) -> usize {
     let mut num_configs = 0;
-    for (index, char) in line.chars().enumerate() {
+    for (index, char) in line.char_indices() {
         if char == '?' || char == '#' {
             let group_window = line.get(index..index + group_size);
             let trailing = line.get(index + group_size..);
@@ -97,39 +97,31 @@ fn count_configurations(line: &str, group_sizes: Vec<usize>) -> usize {
     memo[(height - 1, width - 1)]
 }
 
+/// Each line's spring record and group sizes.
+fn parse_records(contents: &str) -> Vec<(&str, Vec<usize>)> {
+    contents
+        .split('\n')
+        .take_while(|x| !x.is_empty())
+        .map(|line| {
+            let (spring_record, group_sizes) = line.split_once(' ').unwrap();
+            let group_sizes = group_sizes.split(',').map(|x| x.parse().unwrap()).collect();
+            (spring_record, group_sizes)
+        })
+        .collect()
+}
+
 fn part1(contents: String) -> isize {
-    let lines = contents.split('\n').take_while(|x| !x.is_empty());
-    let mut res = 0;
-    for line in lines {
-        let mut line_splits = line.split(' ');
-        let spring_record = line_splits.next().unwrap();
-        let group_sizes: Vec<usize> = line_splits
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|x| x.parse().unwrap())
-            .collect();
-        let line_configurations = count_configurations(spring_record, group_sizes);
-        // dbg!(&line_configurations);
-        res += line_configurations;
-    }
-    res as isize
+    let records = parse_records(&contents);
+    let line_configurations = parallel::map(&records, |(spring_record, group_sizes)| {
+        count_configurations(spring_record, group_sizes.clone())
+    });
+    line_configurations.iter().sum::<usize>() as isize
 }
 
 fn part2(contents: String) -> isize {
-    let lines = contents.split('\n').take_while(|x| !x.is_empty());
-    let mut res = 0;
-    for line in lines {
-        let mut line_splits = line.split(' ');
-        let spring_record = line_splits.next().unwrap();
-        let group_sizes: Vec<usize> = line_splits
-            .next()
-            .unwrap()
-            .split(',')
-            .map(|x| x.parse().unwrap())
-            .collect();
-
-        let mut unfolded_spring_record = String::from(spring_record);
+    let records = parse_records(&contents);
+    let line_configurations = parallel::map(&records, |(spring_record, group_sizes)| {
+        let mut unfolded_spring_record = String::from(*spring_record);
         let mut unfolded_group_sizes = Vec::with_capacity(group_sizes.len() * 5);
         unfolded_group_sizes.extend(group_sizes.iter());
 
@@ -138,12 +130,9 @@ fn part2(contents: String) -> isize {
             unfolded_spring_record.push_str(spring_record);
             unfolded_group_sizes.extend(group_sizes.iter());
         }
-        let line_configurations =
-            count_configurations(&unfolded_spring_record, unfolded_group_sizes);
-        // dbg!(&line_configurations);
-        res += line_configurations;
-    }
-    res as isize
+        count_configurations(&unfolded_spring_record, unfolded_group_sizes)
+    });
+    line_configurations.iter().sum::<usize>() as isize
 }
 
 #[cfg(test)]
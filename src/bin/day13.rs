@@ -1,8 +1,34 @@
-use aoc2023::utils::read_input_file;
+use colored::{ColoredString, Colorize};
+use std::{
+    env,
+    fmt::{self, Display},
+};
+
+use aoc2023::{
+    parallel,
+    utils::{pretty_print, read_input_file},
+};
 use array2d::Array2D;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--report") {
+        let smudges = env::args()
+            .find_map(|x| x.strip_prefix("--smudges=").map(|x| x.parse().unwrap()))
+            .unwrap_or(1);
+        for (i, raw_pattern) in contents
+            .split("\n\n")
+            .take_while(|x| !x.is_empty())
+            .enumerate()
+        {
+            let pattern = Pattern::from_str(raw_pattern);
+            println!("pattern {}:", i + 1);
+            print!("{}", pattern.report(smudges));
+            pretty_print(&pattern.render(pattern.chosen_candidate(smudges).as_ref()));
+            println!();
+        }
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1); // 33728
     let contents = read_input_file(file!(), "input.txt");
@@ -10,140 +36,246 @@ fn main() {
     println!("part 2: {}", part2)
 }
 
-fn find_mirror_row(pattern: &Array2D<char>) -> Option<usize> {
-    (1..pattern.num_rows()).find(|&y| check_mirror_row(pattern, y))
+/// A pattern with each row and column packed into a bitmask, `#` being a set bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Pattern {
+    rows: Vec<u64>,
+    cols: Vec<u64>,
 }
 
-fn find_mirror_row_excluding(pattern: &Array2D<char>, excluded_index: usize) -> Option<usize> {
-    (1..pattern.num_rows()).find(|&y| y != excluded_index && check_mirror_row(pattern, y))
-}
+impl Pattern {
+    pub fn from_str(raw_pattern: &str) -> Self {
+        let lines: Vec<&str> = raw_pattern
+            .split('\n')
+            .take_while(|x| !x.is_empty())
+            .collect();
+        let width = lines[0].len();
+        assert!(
+            lines.len() <= 64 && width <= 64,
+            "patterns have to fit in 64 bit masks"
+        );
+        let mut rows = vec![0; lines.len()];
+        let mut cols = vec![0; width];
+        for (y, line) in lines.iter().enumerate() {
+            for (x, char) in line.char_indices() {
+                if char == '#' {
+                    rows[y] |= 1 << x;
+                    cols[x] |= 1 << y;
+                }
+            }
+        }
+        Pattern { rows, cols }
+    }
 
-fn check_mirror_row(pattern: &Array2D<char>, row_index: usize) -> bool {
-    let mut backtrack_index = row_index - 1;
-    let mut forward_index = row_index;
-    loop {
-        let backtrack_row = match pattern.row_iter(backtrack_index) {
-            Ok(row) => row,
-            Err(_) => break,
-        };
-        let forward_row = match pattern.row_iter(forward_index) {
-            Ok(row) => row,
-            Err(_) => break,
-        };
+    pub fn mirror_row(&self, smudges: u32) -> Option<usize> {
+        find_mirror(&self.rows, smudges)
+    }
 
-        if !backtrack_row.eq(forward_row) {
-            return false;
-        }
-        backtrack_index = backtrack_index.wrapping_sub(1);
-        forward_index += 1;
+    pub fn mirror_col(&self, smudges: u32) -> Option<usize> {
+        find_mirror(&self.cols, smudges)
+    }
+
+    /// The puzzle's score for the first mirror with exactly `smudges` smudges, checking rows
+    /// before columns.
+    pub fn summary(&self, smudges: u32) -> Option<usize> {
+        self.mirror_row(smudges)
+            .map(|y| y * 100)
+            .or_else(|| self.mirror_col(smudges))
     }
-    true
 }
 
-fn find_mirror_col(pattern: &Array2D<char>) -> Option<usize> {
-    (1..pattern.num_columns()).find(|&x| check_mirror_col(pattern, x))
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Axis {
+    /// between two rows
+    Horizontal,
+    /// between two columns
+    Vertical,
 }
 
-fn find_mirror_col_excluding(pattern: &Array2D<char>, excluded_index: usize) -> Option<usize> {
-    (1..pattern.num_columns()).find(|&x| x != excluded_index && check_mirror_col(pattern, x))
+/// A possible line of reflection and the cells that stop it from being one, as `(x, y)`
+/// pairs of a cell before the line and its reflection after it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Candidate {
+    axis: Axis,
+    index: usize,
+    mismatches: Vec<((usize, usize), (usize, usize))>,
 }
 
-fn check_mirror_col(pattern: &Array2D<char>, col_index: usize) -> bool {
-    let mut backtrack_index = col_index - 1;
-    let mut forward_index = col_index;
-    loop {
-        let backtrack_col = match pattern.column_iter(backtrack_index) {
-            Ok(row) => row,
-            Err(_) => break,
-        };
-        let forward_col = match pattern.column_iter(forward_index) {
-            Ok(row) => row,
-            Err(_) => break,
+impl Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let axis = match self.axis {
+            Axis::Horizontal => "horizontal",
+            Axis::Vertical => "vertical",
         };
-
-        if !backtrack_col.eq(forward_col) {
-            return false;
+        write!(
+            f,
+            "{} {}: {} mismatches",
+            axis,
+            self.index,
+            self.mismatches.len()
+        )?;
+        for (i, ((x, y), (mirror_x, mirror_y))) in self.mismatches.iter().enumerate() {
+            if i == 4 {
+                return write!(f, ", ...");
+            }
+            let separator = if i == 0 { " at" } else { "," };
+            write!(
+                f,
+                "{} ({}, {})/({}, {})",
+                separator, x, y, mirror_x, mirror_y
+            )?;
         }
-        backtrack_index = backtrack_index.wrapping_sub(1);
-        forward_index += 1;
+        Ok(())
     }
-    true
 }
 
-fn part1(contents: String) -> isize {
-    let patterns = contents.split("\n\n").take_while(|x| !x.is_empty());
-    let mut res = 0;
-    for raw_pattern in patterns {
-        let height = raw_pattern.matches('\n').count() + 1;
-        let width = raw_pattern.find('\n').unwrap();
-        let pattern = Array2D::from_iter_row_major(
-            raw_pattern.split('\n').flat_map(|x| x.chars()),
-            height,
-            width,
-        )
-        .expect("Creating 2D array for pattern failed!");
-
-        if let Some(mirror_row) = find_mirror_row(&pattern) {
-            res += mirror_row * 100;
-            continue;
-        } else if let Some(mirror_col) = find_mirror_col(&pattern) {
-            res += mirror_col;
-            continue;
-        }
+impl Pattern {
+    fn is_set(&self, x: usize, y: usize) -> bool {
+        self.rows[y] >> x & 1 == 1
     }
-    res as isize
-}
 
-fn part2(contents: String) -> isize {
-    let patterns = contents.split("\n\n").take_while(|x| !x.is_empty());
-    let mut res = 0;
-    for raw_pattern in patterns {
-        let height = raw_pattern.matches('\n').count() + 1;
-        let width = raw_pattern.find('\n').unwrap();
-        let pattern = Array2D::from_iter_row_major(
-            raw_pattern.split('\n').flat_map(|x| x.chars()),
-            height,
-            width,
-        )
-        .expect("Creating 2D array for pattern failed!");
-
-        let original_reflection_score = if let Some(mirror_row) = find_mirror_row(&pattern) {
-            mirror_row * 100
-        } else if let Some(mirror_col) = find_mirror_col(&pattern) {
-            mirror_col
-        } else {
-            panic!("No original reflection line found!")
-        };
+    /// Every possible line of reflection, horizontal ones first, along with everything
+    /// that differs across it.
+    pub fn candidates(&self) -> Vec<Candidate> {
+        let horizontal = (1..self.rows.len()).map(|index| Candidate {
+            axis: Axis::Horizontal,
+            index,
+            mismatches: (0..index.min(self.rows.len() - index))
+                .flat_map(|offset| {
+                    let (y, mirror_y) = (index - 1 - offset, index + offset);
+                    (0..self.cols.len())
+                        .filter(move |&x| self.is_set(x, y) != self.is_set(x, mirror_y))
+                        .map(move |x| ((x, y), (x, mirror_y)))
+                })
+                .collect(),
+        });
+        let vertical = (1..self.cols.len()).map(|index| Candidate {
+            axis: Axis::Vertical,
+            index,
+            mismatches: (0..self.rows.len())
+                .flat_map(|y| {
+                    (0..index.min(self.cols.len() - index))
+                        .map(move |offset| (index - 1 - offset, index + offset))
+                        .filter(move |&(x, mirror_x)| self.is_set(x, y) != self.is_set(mirror_x, y))
+                        .map(move |(x, mirror_x)| ((x, y), (mirror_x, y)))
+                })
+                .collect(),
+        });
+        horizontal.chain(vertical).collect()
+    }
+
+    /// The candidate `summary` scores, if there is one.
+    pub fn chosen_candidate(&self, smudges: u32) -> Option<Candidate> {
+        self.candidates()
+            .into_iter()
+            .find(|x| x.mismatches.len() == smudges as usize)
+    }
 
-        for (y, x) in pattern.indices_row_major() {
-            let mut altered_pattern = pattern.clone();
-            let original_marking = pattern.get(y, x).unwrap();
-            let altered_marking = if *original_marking == '#' { '.' } else { '#' };
-            altered_pattern.set(y, x, altered_marking).unwrap();
-
-            let new_reflection_score = if let Some(mirror_row) =
-                find_mirror_row_excluding(&altered_pattern, original_reflection_score / 100)
-            {
-                mirror_row * 100
-            } else if let Some(mirror_col) =
-                find_mirror_col_excluding(&altered_pattern, original_reflection_score)
-            {
-                mirror_col
+    /// One line per candidate, with the chosen one starred, or a note on the closest
+    /// candidates if none have exactly `smudges` smudges.
+    pub fn report(&self, smudges: u32) -> String {
+        let chosen = self.chosen_candidate(smudges);
+        let mut report = String::new();
+        for candidate in self.candidates() {
+            let marker = if Some(&candidate) == chosen.as_ref() {
+                "*"
             } else {
-                continue;
+                " "
             };
-            if new_reflection_score != original_reflection_score {
-                res += new_reflection_score;
-                break;
+            report += &format!("{} {}\n", marker, candidate);
+        }
+        if chosen.is_none() {
+            let closest = self.candidates().iter().map(|x| x.mismatches.len()).min();
+            report += &match closest {
+                Some(closest) => format!(
+                    "no line has exactly {} smudges, the closest has {}\n",
+                    smudges, closest
+                ),
+                None => "the pattern is too small to have a line of reflection\n".to_owned(),
+            };
+        }
+        report
+    }
+
+    /// The pattern with the lines either side of `chosen` in cyan and its smudges in red.
+    pub fn render(&self, chosen: Option<&Candidate>) -> Array2D<ColoredString> {
+        let mut rendered = Array2D::filled_with(".".normal(), self.rows.len(), self.cols.len());
+        for y in 0..self.rows.len() {
+            for x in 0..self.cols.len() {
+                let tile = if self.is_set(x, y) { "#" } else { "." };
+                let beside_axis = chosen.is_some_and(|chosen| match chosen.axis {
+                    Axis::Horizontal => y + 1 == chosen.index || y == chosen.index,
+                    Axis::Vertical => x + 1 == chosen.index || x == chosen.index,
+                });
+                let smudged = chosen.is_some_and(|chosen| {
+                    chosen
+                        .mismatches
+                        .iter()
+                        .any(|&(cell, mirror)| cell == (x, y) || mirror == (x, y))
+                });
+                rendered[(y, x)] = if smudged {
+                    tile.bold().on_red()
+                } else if beside_axis {
+                    tile.cyan()
+                } else {
+                    tile.normal()
+                };
             }
         }
+        rendered
     }
-    res as isize
+}
+
+/// How many cells differ between `lines` and their reflection across the axis just before
+/// `index`, giving up once it's past `limit`.
+fn count_mismatches(lines: &[u64], index: usize, limit: u32) -> u32 {
+    let mut mismatches = 0;
+    for (before, after) in lines[..index].iter().rev().zip(lines[index..].iter()) {
+        mismatches += (before ^ after).count_ones();
+        if mismatches > limit {
+            break;
+        }
+    }
+    mismatches
+}
+
+/// The first axis that `lines` reflect across once exactly `smudges` cells are fixed.
+fn find_mirror(lines: &[u64], smudges: u32) -> Option<usize> {
+    (1..lines.len()).find(|&index| count_mismatches(lines, index, smudges) == smudges)
+}
+
+fn summarize(contents: &str, smudges: u32) -> usize {
+    let raw_patterns: Vec<&str> = contents
+        .split("\n\n")
+        .take_while(|x| !x.is_empty())
+        .collect();
+    parallel::map(&raw_patterns, |raw_pattern| {
+        Pattern::from_str(raw_pattern)
+            .summary(smudges)
+            .unwrap_or_else(|| {
+                panic!(
+                    "No reflection line found!\n{}",
+                    Pattern::from_str(raw_pattern).report(smudges)
+                )
+            })
+    })
+    .iter()
+    .sum()
+}
+
+fn part1(contents: String) -> isize {
+    summarize(&contents, 0) as isize
+}
+
+fn part2(contents: String) -> isize {
+    summarize(&contents, 1) as isize
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use colored::Color;
+
+    use crate::{part1, part2, read_input_file, Axis, Pattern};
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
@@ -180,4 +312,47 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn more_smudges() {
+        // the rows are one cell off a mirror, the columns around x = 1 are two off
+        let pattern = Pattern::from_str("#.##\n#..#");
+        assert_eq!(pattern.summary(0), None);
+        assert_eq!(pattern.summary(1), Some(100));
+        assert_eq!(pattern.mirror_col(1), Some(2));
+        assert_eq!(pattern.summary(2), Some(1));
+        assert_eq!(pattern.summary(3), None);
+    }
+
+    #[test]
+    fn report_sample() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let pattern = Pattern::from_str(contents.split("\n\n").next().unwrap());
+        let candidates = pattern.candidates();
+        assert_eq!(candidates.len(), 6 + 8);
+        for candidate in candidates.iter() {
+            let summary = match candidate.axis {
+                Axis::Horizontal => pattern.mirror_row(candidate.mismatches.len() as u32),
+                Axis::Vertical => pattern.mirror_col(candidate.mismatches.len() as u32),
+            };
+            assert!(summary.is_some_and(|x| x <= candidate.index));
+        }
+
+        let chosen = pattern.chosen_candidate(1).unwrap();
+        assert_eq!(chosen.axis, Axis::Horizontal);
+        assert_eq!(chosen.index, 3);
+        assert_eq!(chosen.mismatches, [((0, 0), (0, 5))]);
+        let report = pattern.report(1);
+        assert!(report.contains("* horizontal 3: 1 mismatches at (0, 0)/(0, 5)\n"));
+        assert!(report.contains("  vertical 5: 0 mismatches\n"));
+        assert!(pattern
+            .report(50)
+            .ends_with("no line has exactly 50 smudges, the closest has 0\n"));
+
+        let rendered = pattern.render(Some(&chosen));
+        assert_eq!(rendered[(0, 0)].bgcolor, Some(Color::Red));
+        assert_eq!(rendered[(5, 0)].bgcolor, Some(Color::Red));
+        assert_eq!(rendered[(2, 4)].fgcolor, Some(Color::Cyan));
+        assert_eq!(rendered[(1, 4)].fgcolor, None);
+    }
 }
@@ -1,10 +1,34 @@
-use std::collections::HashMap;
+use std::{
+    env,
+    fmt::{self, Display},
+    fs, thread,
+    time::Duration,
+};
 
-use aoc2023::utils::read_input_file;
-use array2d::Array2D;
+use aoc2023::{
+    cycles::{find_cycle_hashed, nth_state},
+    utils::read_input_file,
+};
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--animate") {
+        let frames = animation_frames(&Platform::from_str(&contents));
+        if let Some(path) = env::args().find_map(|x| x.strip_prefix("--frames=").map(str::to_owned))
+        {
+            fs::write(path, frames.join("\n")).unwrap();
+            return;
+        }
+        let delay = env::args()
+            .find_map(|x| x.strip_prefix("--delay=").map(|x| x.parse().unwrap()))
+            .unwrap_or(100);
+        for frame in frames {
+            // clear the screen and go back to the top left before each frame
+            print!("\x1b[2J\x1b[H{}", frame);
+            thread::sleep(Duration::from_millis(delay));
+        }
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1);
     let contents = read_input_file(file!(), "input.txt");
@@ -12,7 +36,7 @@ fn main() {
     println!("part 2: {}", part2)
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Direction {
     North,
     East,
@@ -20,144 +44,181 @@ pub enum Direction {
     West,
 }
 
-fn part1(contents: String) -> isize {
-    let lines = contents.split('\n').take_while(|x| !x.is_empty());
-    let height = contents.matches('\n').count();
-    let width = contents.find('\n').unwrap();
-    let mut map =
-        Array2D::from_iter_row_major(lines.flat_map(|x| x.chars()), height, width).unwrap();
-
-    let mut res = 0;
-    // dbg!(&map);
-    for x in 0..width {
-        let mut rock_column: Vec<char> = map.column_iter(x).unwrap().cloned().collect();
-        let mut landing_area = 0;
-        for y in 0..height {
-            let current_rock = rock_column[y];
-            if current_rock == '#' {
-                landing_area = y + 1
-            } else if current_rock == 'O' {
-                if landing_area < y {
-                    rock_column[y] = '.';
-                    rock_column[landing_area] = 'O';
-                }
-                while rock_column
-                    .get(landing_area)
-                    .is_some_and(|landing_rock| *landing_rock != '.')
-                {
-                    landing_area += 1
-                }
-            }
-        }
-        for (y, &new_rock) in rock_column.iter().enumerate() {
-            map.set(y, x, new_rock).expect("Setting rock column failed");
-            if new_rock == 'O' {
-                res += height - y;
-            }
-        }
-    }
-    // dbg!(&map);
-    res as isize
+const SPIN_ORDER: [Direction; 4] = [
+    Direction::North,
+    Direction::West,
+    Direction::South,
+    Direction::East,
+];
+
+/// The platform as one pair of bitsets per row, with bit `x` standing for column `x`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Platform {
+    width: usize,
+    round: Vec<u128>,
+    cube: Vec<u128>,
 }
 
-fn tilt_direction(map: &mut Array2D<char>, direction: Direction) {
-    let width = map.num_columns();
-    let height = map.num_rows();
-    let is_vertical = direction == Direction::North || direction == Direction::South;
-    let is_inverted = direction == Direction::South || direction == Direction::East;
-    for i in 0..(if is_vertical { width } else { height }) {
-        let mut rock_line: Vec<char> = if is_vertical {
-            map.column_iter(i).unwrap().cloned().collect()
-        } else {
-            map.row_iter(i).unwrap().cloned().collect()
+impl Platform {
+    pub fn from_str(contents: &str) -> Self {
+        let lines: Vec<&str> = contents.split('\n').take_while(|x| !x.is_empty()).collect();
+        let width = lines[0].len();
+        assert!(width <= 128, "rows have to fit in a u128");
+        let mask_of = |line: &str, rock: char| {
+            line.char_indices()
+                .filter(|&(_, x)| x == rock)
+                .fold(0, |mask, (x, _)| mask | 1 << x)
         };
-
-        if is_inverted {
-            rock_line.reverse();
+        Platform {
+            width,
+            round: lines.iter().map(|x| mask_of(x, 'O')).collect(),
+            cube: lines.iter().map(|x| mask_of(x, '#')).collect(),
         }
-        let mut landing_area = 0;
-        for j in 0..(if is_vertical { height } else { width }) {
-            let current_rock = rock_line[j];
-            if current_rock == '#' {
-                landing_area = j + 1
-            } else if current_rock == 'O' {
-                if landing_area < j {
-                    rock_line[j] = '.';
-                    rock_line[landing_area] = 'O';
+    }
+
+    fn height(&self) -> usize {
+        self.round.len()
+    }
+
+    /// Every column that's actually on the platform.
+    fn row_mask(&self) -> u128 {
+        u128::MAX >> (128 - self.width)
+    }
+
+    pub fn tilt(&mut self, direction: Direction) {
+        match direction {
+            Direction::North => self.tilt_vertical((0..self.height()).collect()),
+            Direction::South => self.tilt_vertical((0..self.height()).rev().collect()),
+            Direction::West | Direction::East => {
+                let row_mask = self.row_mask();
+                for (round, &cube) in self.round.iter_mut().zip(self.cube.iter()) {
+                    // every rock with a gap on the downhill side moves into it at once, until
+                    // none of them can
+                    loop {
+                        let empty = !(*round | cube) & row_mask;
+                        let moving = if direction == Direction::West {
+                            *round & empty << 1
+                        } else {
+                            *round & empty >> 1
+                        };
+                        if moving == 0 {
+                            break;
+                        }
+                        *round &= !moving;
+                        *round |= if direction == Direction::West {
+                            moving >> 1
+                        } else {
+                            moving << 1
+                        };
+                    }
                 }
-                while rock_line
-                    .get(landing_area)
-                    .is_some_and(|landing_rock| *landing_rock != '.')
-                {
-                    landing_area += 1;
+            }
+        }
+    }
+
+    /// Tilts so rocks roll towards the first row in `order`. Each row's rocks sink through
+    /// the rows before it, which have already settled, as far as they can.
+    fn tilt_vertical(&mut self, order: Vec<usize>) {
+        for i in 1..order.len() {
+            let mut moving = self.round[order[i]];
+            for j in (0..i).rev() {
+                let (from, to) = (order[j + 1], order[j]);
+                moving &= !(self.round[to] | self.cube[to]);
+                if moving == 0 {
+                    break;
                 }
+                self.round[from] &= !moving;
+                self.round[to] |= moving;
             }
         }
-        if is_inverted {
-            rock_line.reverse();
+    }
+
+    pub fn spin_cycle(&mut self) {
+        for direction in SPIN_ORDER {
+            self.tilt(direction);
         }
+    }
 
-        for (j, &new_rock) in rock_line.iter().enumerate() {
-            if is_vertical {
-                map.set(j, i, new_rock).expect("Setting rock line failed");
-            } else {
-                map.set(i, j, new_rock).expect("Setting rock line failed");
+    pub fn get_north_load(&self) -> usize {
+        self.round
+            .iter()
+            .enumerate()
+            .map(|(y, row)| row.count_ones() as usize * (self.height() - y))
+            .sum()
+    }
+}
+
+impl Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (round, cube) in self.round.iter().zip(self.cube.iter()) {
+            for x in 0..self.width {
+                let tile = if round >> x & 1 == 1 {
+                    'O'
+                } else if cube >> x & 1 == 1 {
+                    '#'
+                } else {
+                    '.'
+                };
+                write!(f, "{}", tile)?;
             }
+            writeln!(f)?;
         }
+        Ok(())
     }
 }
 
-fn spin_cycle(map: &mut Array2D<char>) {
-    let directions = [
-        Direction::North,
-        Direction::West,
-        Direction::South,
-        Direction::East,
-    ];
-    for direction in directions {
-        tilt_direction(map, direction);
-    }
+fn part1(contents: String) -> isize {
+    let mut platform = Platform::from_str(&contents);
+    platform.tilt(Direction::North);
+    platform.get_north_load() as isize
 }
 
-fn get_north_load(map: &Array2D<char>) -> usize {
-    let height = map.num_rows();
-    map.enumerate_row_major()
-        .fold(0, |sum, ((y, _), &terrain)| {
-            if terrain == 'O' {
-                sum + height - y
-            } else {
-                sum
-            }
-        })
+fn spun(platform: &Platform) -> Platform {
+    let mut next = platform.clone();
+    next.spin_cycle();
+    next
 }
 
-fn part2(contents: String) -> isize {
-    let lines = contents.split('\n').take_while(|x| !x.is_empty());
-    let height = contents.matches('\n').count();
-    let width = contents.find('\n').unwrap();
-    let mut map =
-        Array2D::from_iter_row_major(lines.flat_map(|x| x.chars()), height, width).unwrap();
-
-    let mut seen_states: HashMap<Array2D<char>, usize> = HashMap::new();
-    let mut i = 1000000000;
-    while i > 0 {
-        if let Some(&last_seen_time) = seen_states.get(&map) {
-            let cycle_time = last_seen_time - i;
-            i %= cycle_time;
-            if i == 0 {
-                break;
-            }
+/// The platform after every tilt of every spin cycle, up until the cycle detector sees a
+/// state it's been in before, each with a header saying where it's up to.
+fn animation_frames(platform: &Platform) -> Vec<String> {
+    let header = |cycle: usize, tilt: Option<Direction>, platform: &Platform| {
+        let tilt = tilt.map_or("start".to_owned(), |x| format!("tilted {:?}", x));
+        format!(
+            "cycle {}, {}, north load {}\n",
+            cycle,
+            tilt,
+            platform.get_north_load()
+        )
+    };
+    let mut frames = vec![header(0, None, platform) + &platform.to_string()];
+    let mut cycle = 0;
+    let (prefix, period) = find_cycle_hashed(platform, |platform| {
+        cycle += 1;
+        let mut next = platform.clone();
+        for direction in SPIN_ORDER {
+            next.tilt(direction);
+            frames.push(header(cycle, Some(direction), &next) + &next.to_string());
         }
-        seen_states.insert(map.clone(), i);
-        spin_cycle(&mut map);
-        i -= 1;
-    }
-    get_north_load(&map) as isize
+        next
+    });
+    frames.last_mut().unwrap().push_str(&format!(
+        "repeat found: this is the platform after cycle {}, so it loops every {} cycles\n",
+        prefix, period
+    ));
+    frames
+}
+
+fn part2(contents: String) -> isize {
+    let platform = Platform::from_str(&contents);
+    nth_state(&platform, 1000000000, spun).get_north_load() as isize
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use aoc2023::cycles::{find_cycle, find_cycle_hashed};
+
+    use crate::{animation_frames, part1, part2, read_input_file, spun, Direction, Platform};
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
@@ -194,4 +255,70 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn sample_spin_cycle() {
+        let platform = Platform::from_str(&read_input_file(file!(), "sample.txt"));
+        assert_eq!(find_cycle(&platform, spun), (3, 7));
+        assert_eq!(find_cycle_hashed(&platform, spun), (3, 7));
+    }
+
+    #[test]
+    fn each_tilt() {
+        let mut platform = Platform::from_str(&read_input_file(file!(), "sample.txt"));
+        platform.tilt(Direction::North);
+        assert!(platform
+            .to_string()
+            .starts_with("OOOO.#.O..\nOO..#....#\nOO..O##..O\n"));
+        platform.tilt(Direction::West);
+        assert!(platform
+            .to_string()
+            .starts_with("OOOO.#O...\nOO..#....#\nOOO..##O..\n"));
+        platform.tilt(Direction::South);
+        assert!(platform
+            .to_string()
+            .ends_with("OO....OO..\n#O...###..\n#O..O#....\n"));
+        platform.tilt(Direction::East);
+        assert_eq!(
+            platform.to_string(),
+            ".....#....\n....#...O#\n...OO##...\n.OO#......\n.....OOO#.\n\
+             .O#...O#.#\n....O#....\n......OOOO\n#...O###..\n#..OO#....\n"
+        );
+        platform.spin_cycle();
+        platform.spin_cycle();
+        assert_eq!(
+            platform.to_string(),
+            ".....#....\n....#...O#\n.....##...\n..O#......\n.....OOO#.\n\
+             .O#...O#.#\n....O#...O\n.......OOO\n#...O###.O\n#.OOO#...O\n"
+        );
+    }
+
+    #[test]
+    fn full_width_rows() {
+        // 128 columns is the most a row holds, so shifts have to keep rocks on the platform
+        let row = format!("O{}O", ".".repeat(126));
+        let mut platform = Platform::from_str(&format!("{}\n", row));
+        platform.tilt(Direction::East);
+        assert_eq!(platform.to_string(), format!("{}OO\n", ".".repeat(126)));
+        platform.tilt(Direction::West);
+        assert_eq!(platform.to_string(), format!("OO{}\n", ".".repeat(126)));
+    }
+
+    #[test]
+    fn sample_animation() {
+        let platform = Platform::from_str(&read_input_file(file!(), "sample.txt"));
+        let frames = animation_frames(&platform);
+        // the sample settles into a loop of 7 after 3 cycles, and each cycle is 4 tilts
+        assert_eq!(frames.len(), 1 + 4 * (3 + 7));
+        assert!(frames[0].starts_with(&format!(
+            "cycle 0, start, north load {}\nO....#....\n",
+            platform.get_north_load()
+        )));
+        assert!(frames[1].starts_with("cycle 1, tilted North, north load 136\nOOOO.#.O..\n"));
+        assert!(frames[4].starts_with("cycle 1, tilted East, north load 87\n"));
+        assert!(frames[4].ends_with("#..OO#....\n"));
+        assert!(frames[40].ends_with(
+            "repeat found: this is the platform after cycle 3, so it loops every 7 cycles\n"
+        ));
+    }
 }
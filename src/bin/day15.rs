@@ -1,7 +1,20 @@
+#![feature(hasher_prefixfree_extras)]
+
+use std::{
+    env,
+    fmt::{self, Display},
+    hash::{BuildHasher, Hasher},
+};
+
 use aoc2023::utils::read_input_file;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--trace") {
+        let label = env::args().find_map(|x| x.strip_prefix("--label=").map(str::to_owned));
+        print!("{}", trace(&contents, label.as_deref()));
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1); // 506869
     let contents = read_input_file(file!(), "input.txt");
@@ -9,75 +22,193 @@ fn main() {
     println!("part 2: {}", part2)
 }
 
-fn hash(input: &str) -> u8 {
-    let mut res: usize = 0;
-    for char in input.chars() {
-        res += char as usize;
-        res *= 17;
-        res &= 255;
-    }
-    res as u8
+/// The Holiday ASCII String Helper algorithm as a `Hasher`, so it can also drive the
+/// standard collections.
+#[derive(Debug, Default, Clone, Copy)]
+struct HolidayHasher {
+    state: u8,
 }
-fn part1(contents: String) -> isize {
-    let clauses = contents.trim_end().split(',');
-    let mut res: isize = 0;
-    for clause in clauses {
-        res += hash(clause) as isize;
+
+impl Hasher for HolidayHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.state = self.state.wrapping_add(byte).wrapping_mul(17);
+        }
+    }
+
+    /// Hashes just the characters, without the terminator `str` normally adds, so a `&str`
+    /// key hashes the same as the puzzle says.
+    fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.state as u64
     }
-    res
 }
 
-fn part2(contents: String) -> isize {
-    let clauses = contents.trim_end().split(',');
-    let num_boxes = 256;
-    let mut boxes: Vec<Vec<(&str, u8)>> = Vec::with_capacity(num_boxes);
-    for _ in 0..num_boxes {
-        boxes.push(Vec::new());
+#[derive(Debug, Default, Clone, Copy)]
+struct BuildHolidayHasher;
+
+impl BuildHasher for BuildHolidayHasher {
+    type Hasher = HolidayHasher;
+
+    fn build_hasher(&self) -> HolidayHasher {
+        HolidayHasher::default()
     }
+}
 
-    for clause in clauses {
-        let operation = if clause.contains('=') { '=' } else { '-' };
-        let end_of_key = if operation == '=' {
-            clause.find('=').unwrap()
+fn hash(input: &str) -> u8 {
+    BuildHolidayHasher.hash_one(input) as u8
+}
+
+/// One step of the initialization sequence, like `rn=1` or `cm-`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step<'a> {
+    Insert(&'a str, u8),
+    Remove(&'a str),
+}
+
+impl<'a> Step<'a> {
+    pub fn from_str(step: &'a str) -> Self {
+        if let Some((label, focal_length)) = step.split_once('=') {
+            Step::Insert(label, focal_length.parse().unwrap())
         } else {
-            clause.find('-').unwrap()
-        };
-
-        let label = &clause[0..end_of_key];
-        let key = hash(label) as usize;
-        if operation == '=' {
-            let new_focal_length: u8 = clause[end_of_key + 1..].parse().unwrap();
-            if let Some(existing_lens_index) = boxes[key]
-                .iter()
-                .position(|(other_label, _)| *other_label == label)
-            {
-                boxes[key][existing_lens_index] = (label, new_focal_length);
-            } else {
-                boxes[key].push((label, new_focal_length));
+            Step::Remove(step.strip_suffix('-').expect("steps are either = or -"))
+        }
+    }
+
+    pub fn label(&self) -> &'a str {
+        match self {
+            Step::Insert(label, _) | Step::Remove(label) => label,
+        }
+    }
+}
+
+/// The HASHMAP: 256 boxes picked by the HASH of each lens's label, with each box keeping its
+/// lenses in the order they were first put in.
+#[derive(Debug, Clone)]
+struct LensMap {
+    boxes: Vec<Vec<(String, u8)>>,
+}
+
+impl LensMap {
+    pub fn new() -> Self {
+        LensMap {
+            boxes: vec![Vec::new(); 256],
+        }
+    }
+
+    /// Puts a lens in its box, replacing any lens with the same label where it stands, and
+    /// returns the replaced lens's focal length.
+    pub fn insert(&mut self, label: &str, focal_length: u8) -> Option<u8> {
+        let lenses = &mut self.boxes[hash(label) as usize];
+        match lenses.iter_mut().find(|(other, _)| other == label) {
+            Some((_, existing)) => Some(std::mem::replace(existing, focal_length)),
+            None => {
+                lenses.push((label.to_owned(), focal_length));
+                None
+            }
+        }
+    }
+
+    /// Takes a lens out of its box, moving the lenses behind it forward, and returns its
+    /// focal length.
+    pub fn remove(&mut self, label: &str) -> Option<u8> {
+        let lenses = &mut self.boxes[hash(label) as usize];
+        let index = lenses.iter().position(|(other, _)| other == label)?;
+        Some(lenses.remove(index).1)
+    }
+
+    pub fn apply(&mut self, step: Step) {
+        match step {
+            Step::Insert(label, focal_length) => {
+                self.insert(label, focal_length);
             }
-        } else if operation == '-' {
-            if let Some(remove_index) = boxes[key]
-                .iter()
-                .position(|(other_label, _)| *other_label == label)
-            {
-                boxes[key as usize].remove(remove_index);
+            Step::Remove(label) => {
+                self.remove(label);
             }
         }
     }
 
-    let mut res = 0;
+    /// Every lens as `(box number, slot, label, focal length)`, box by box, front to back.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &str, u8)> {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(box_number, lenses)| {
+                lenses
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, (label, focal_length))| {
+                        (box_number, slot, label.as_str(), *focal_length)
+                    })
+            })
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        self.iter()
+            .map(|(box_number, slot, _, focal_length)| {
+                (box_number + 1) * (slot + 1) * focal_length as usize
+            })
+            .sum()
+    }
+}
+
+/// Every box with something in it, like the puzzle shows them: `Box 3: [ot 7] [ab 5]`.
+impl Display for LensMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (box_number, lenses) in self.boxes.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+            write!(f, "Box {}:", box_number)?;
+            for (label, focal_length) in lenses {
+                write!(f, " [{} {}]", label, focal_length)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
 
-    for (box_number, box_lenses) in boxes.iter().enumerate() {
-        for (lens_position, (_, focal_length)) in box_lenses.iter().enumerate() {
-            res += (box_number + 1) * (lens_position + 1) * (*focal_length as usize);
+/// The boxes after each step of the initialization sequence in the puzzle's format, or only
+/// after the steps for `label` if one is given.
+fn trace(contents: &str, label: Option<&str>) -> String {
+    let mut lenses = LensMap::new();
+    let mut res = String::new();
+    for clause in contents.trim_end().split(',') {
+        let step = Step::from_str(clause);
+        lenses.apply(step);
+        if label.is_none_or(|x| x == step.label()) {
+            res += &format!("After \"{}\":\n{}\n", clause, lenses);
         }
     }
-    res as isize
+    res
+}
+
+fn part1(contents: String) -> isize {
+    let clauses = contents.trim_end().split(',');
+    let mut res: isize = 0;
+    for clause in clauses {
+        res += hash(clause) as isize;
+    }
+    res
+}
+
+fn part2(contents: String) -> isize {
+    let mut lenses = LensMap::new();
+    for clause in contents.trim_end().split(',') {
+        lenses.apply(Step::from_str(clause));
+    }
+    lenses.focusing_power() as isize
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use std::{collections::HashMap, hash::BuildHasher};
+
+    use crate::{part1, part2, read_input_file, trace, BuildHolidayHasher, LensMap, Step};
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
@@ -114,4 +245,94 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn hasher() {
+        assert_eq!(BuildHolidayHasher.hash_one("HASH"), 52);
+        assert_eq!(BuildHolidayHasher.hash_one("rn"), 0);
+        assert_eq!(BuildHolidayHasher.hash_one("qp"), 1);
+        assert_eq!(BuildHolidayHasher.hash_one("rn=1"), 30);
+        let mut map: HashMap<&str, u8, BuildHolidayHasher> = HashMap::default();
+        map.insert("rn", 1);
+        map.insert("cm", 2);
+        map.insert("rn", 3);
+        assert_eq!(map.get("rn"), Some(&3));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn parse_steps() {
+        assert_eq!(Step::from_str("rn=1"), Step::Insert("rn", 1));
+        assert_eq!(Step::from_str("cm-"), Step::Remove("cm"));
+    }
+
+    #[test]
+    fn insert_keeps_position() {
+        let mut lenses = LensMap::new();
+        // rn and cm both hash to box 0
+        assert_eq!(lenses.insert("rn", 1), None);
+        assert_eq!(lenses.insert("cm", 2), None);
+        assert_eq!(lenses.insert("rn", 5), Some(1));
+        let contents: Vec<_> = lenses.iter().collect();
+        assert_eq!(contents, [(0, 0, "rn", 5), (0, 1, "cm", 2)]);
+    }
+
+    #[test]
+    fn remove_shifts_lenses_forward() {
+        let mut lenses = LensMap::new();
+        lenses.insert("rn", 1);
+        lenses.insert("cm", 2);
+        lenses.insert("qp", 3);
+        assert_eq!(lenses.remove("rn"), Some(1));
+        assert_eq!(lenses.remove("rn"), None);
+        assert_eq!(lenses.remove("pc"), None);
+        let contents: Vec<_> = lenses.iter().collect();
+        assert_eq!(contents, [(0, 0, "cm", 2), (1, 0, "qp", 3)]);
+        lenses.insert("rn", 4);
+        let contents: Vec<_> = lenses.iter().collect();
+        assert_eq!(
+            contents,
+            [(0, 0, "cm", 2), (0, 1, "rn", 4), (1, 0, "qp", 3)]
+        );
+    }
+
+    #[test]
+    fn focusing_power() {
+        let mut lenses = LensMap::new();
+        assert_eq!(lenses.focusing_power(), 0);
+        for step in "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".split(',') {
+            lenses.apply(Step::from_str(step));
+        }
+        let contents: Vec<_> = lenses.iter().collect();
+        assert_eq!(
+            contents,
+            [
+                (0, 0, "rn", 1),
+                (0, 1, "cm", 2),
+                (3, 0, "ot", 7),
+                (3, 1, "ab", 5),
+                (3, 2, "pc", 6)
+            ]
+        );
+        assert_eq!(lenses.focusing_power(), 145);
+    }
+
+    #[test]
+    fn trace_sample() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let full = trace(&contents, None);
+        assert!(full.starts_with(
+            "After \"rn=1\":\nBox 0: [rn 1]\n\n\
+             After \"cm-\":\nBox 0: [rn 1]\n\n\
+             After \"qp=3\":\nBox 0: [rn 1]\nBox 1: [qp 3]\n\n"
+        ));
+        assert!(full
+            .ends_with("After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n\n"));
+        assert_eq!(full.matches("After").count(), 11);
+
+        let only_pc = trace(&contents, Some("pc"));
+        assert_eq!(only_pc.matches("After").count(), 3);
+        assert!(only_pc.starts_with("After \"pc=4\":\nBox 0: [rn 1] [cm 2]\nBox 3: [pc 4]\n\n"));
+        assert_eq!(trace(&contents, Some("zz")), "");
+    }
 }
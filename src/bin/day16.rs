@@ -1,22 +1,46 @@
+use colored::{ColoredString, Colorize};
 use std::{
-    cmp::max,
     collections::{HashMap, HashSet},
+    env,
+    fmt::{self, Display},
     time::Instant,
 };
 
-use aoc2023::utils::{read_2d_map, read_input_file};
+use aoc2023::{
+    parallel,
+    utils::{pretty_print, read_2d_map, read_input_file},
+};
 use array2d::Array2D;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--render") {
+        let map = read_2d_map(contents);
+        let start = if env::args().any(|x| x == "--best") {
+            best_start(&map).0
+        } else {
+            Beam {
+                position: Coord::new(0, 0),
+                direction: Direction::East,
+            }
+        };
+        pretty_print(&render(&map, start));
+        println!("starting {}", start);
+        println!(
+            "energized tiles: {}",
+            BeamGraph::new(&map).energized(start).len()
+        );
+        return;
+    }
     let part1 = part1(contents);
     println!("part 1: {}", part1); // 8249
     let contents = read_input_file(file!(), "input.txt");
     let start2 = Instant::now();
-    let part2 = part2(contents);
+    let (best, part2) = best_start(&read_2d_map(contents));
     let duration2 = start2.elapsed();
     println!("Time elapsed for part 2: {:?}", duration2);
-    println!("part 2: {}", part2)
+    println!("part 2: {}", part2);
+    println!("best start: {}", best)
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -43,6 +67,15 @@ impl Direction {
     pub fn is_vertical(&self) -> bool {
         *self == Direction::North || *self == Direction::South
     }
+
+    pub fn arrow(&self) -> char {
+        match self {
+            Direction::North => '^',
+            Direction::West => '<',
+            Direction::South => 'v',
+            Direction::East => '>',
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -102,128 +135,415 @@ impl Beam {
         Some(new_beam)
     }
 }
-fn part1(contents: String) -> isize {
-    let map = read_2d_map(contents);
-    count_energized_tiles(
-        &map,
-        Beam {
-            position: Coord::new(0, 0),
-            direction: Direction::East,
-        },
-    )
+
+impl Display for Beam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "at ({}, {}) heading {:?}",
+            self.position.x, self.position.y, self.direction
+        )
+    }
 }
 
-fn count_energized_tiles(map: &Array2D<char>, starting_beam: Beam) -> isize {
-    let mut energized_tiles: HashMap<Coord, HashSet<Direction>> = HashMap::new();
-    let mut beams: Vec<Beam> = vec![starting_beam];
-    while !beams.is_empty() {
-        let mut future_beams: Vec<Beam> = Vec::new();
-        let mut removed_beams: HashSet<Beam> = HashSet::new();
-
-        for beam in beams.iter_mut() {
-            let Coord { x, y } = beam.position;
-            if energized_tiles
-                .get(&beam.position)
-                .is_some_and(|directions| directions.contains(&beam.direction))
-            {
-                removed_beams.insert(*beam);
-                continue;
+/// A set of tiles on a map `width` wide, one bit per tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TileSet {
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    pub fn new(width: usize, height: usize) -> Self {
+        TileSet {
+            width,
+            bits: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, tile: Coord) {
+        let index = tile.y * self.width + tile.x;
+        self.bits[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn union_with(&mut self, other: &TileSet) {
+        for (bits, other_bits) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *bits |= other_bits;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|x| x.count_ones() as usize).sum()
+    }
+}
+
+/// The contraption boiled down to what happens at splitters. A beam that hits a splitter
+/// side on always leaves the same two ways, so each splitter is a node whose edges go to
+/// the splitters those two beams hit next. Splitters that keep sending beams round to each
+/// other are collapsed into one component, and every component knows every tile lit once a
+/// beam reaches it, so any number of starts can share the work.
+struct BeamGraph<'a> {
+    map: &'a Array2D<char>,
+    /// the node for each splitter
+    nodes: HashMap<Coord, usize>,
+    /// the strongly connected component each node is in
+    component: Vec<usize>,
+    /// every tile lit by a beam that reaches each component
+    component_tiles: Vec<TileSet>,
+}
+
+impl<'a> BeamGraph<'a> {
+    pub fn new(map: &'a Array2D<char>) -> Self {
+        let splitters: Vec<Coord> = map
+            .enumerate_row_major()
+            .filter(|(_, &tile)| tile == '|' || tile == '-')
+            .map(|((y, x), _)| Coord::new(x, y))
+            .collect();
+        let nodes: HashMap<Coord, usize> = splitters
+            .iter()
+            .enumerate()
+            .map(|(i, &splitter)| (splitter, i))
+            .collect();
+        let mut graph = BeamGraph {
+            map,
+            nodes,
+            component: Vec::new(),
+            component_tiles: Vec::new(),
+        };
+
+        let mut edges: Vec<Vec<usize>> = Vec::with_capacity(splitters.len());
+        let mut node_tiles: Vec<TileSet> = Vec::with_capacity(splitters.len());
+        for &splitter in splitters.iter() {
+            let mut tiles = TileSet::new(map.num_columns(), map.num_rows());
+            tiles.insert(splitter);
+            let mut successors = Vec::new();
+            for beam in graph.split_beams(splitter) {
+                let (segment, hit) = graph.trace_segment(beam);
+                segment.iter().for_each(|&tile| tiles.insert(tile));
+                successors.extend(hit);
             }
+            edges.push(successors);
+            node_tiles.push(tiles);
+        }
 
-            if let Some(current_space) = map.get(y, x) {
-                let position_entry = energized_tiles
-                    .entry(beam.position)
-                    .or_insert_with(|| HashSet::with_capacity(4));
-                position_entry.insert(beam.direction);
-                match current_space {
-                    '.' => beam.shift(),
-                    '/' | '\\' => {
-                        beam.reflect(*current_space);
-                        beam.shift();
+        let components = strongly_connected_components(&edges);
+        graph.component = vec![0; splitters.len()];
+        for (i, members) in components.iter().enumerate() {
+            for &node in members {
+                graph.component[node] = i;
+            }
+        }
+        // components come out with everything they lead to before them, so each one only
+        // has to add its own tiles to ones that are already finished
+        for members in components.iter() {
+            let mut tiles = TileSet::new(map.num_columns(), map.num_rows());
+            for &node in members {
+                tiles.union_with(&node_tiles[node]);
+                for &next in edges[node].iter() {
+                    let next_component = graph.component[next];
+                    if next_component != graph.component[node] {
+                        tiles.union_with(&graph.component_tiles[next_component]);
                     }
-                    '|' | '-' => {
-                        if let Some(split_beam) = beam.handle_split(*current_space) {
-                            future_beams.push(split_beam)
-                        }
+                }
+            }
+            graph.component_tiles.push(tiles);
+        }
+        graph
+    }
+
+    /// The two beams that leave `splitter` when something hits it side on.
+    fn split_beams(&self, splitter: Coord) -> [Beam; 2] {
+        let direction = if self.map[(splitter.y, splitter.x)] == '|' {
+            Direction::East
+        } else {
+            Direction::South
+        };
+        let mut beam = Beam {
+            position: splitter,
+            direction,
+        };
+        let mut other = beam
+            .handle_split(self.map[(splitter.y, splitter.x)])
+            .unwrap();
+        beam.shift();
+        other.shift();
+        [beam, other]
+    }
+
+    /// Follows `beam` until it leaves the map, goes round in a loop, or hits a splitter side
+    /// on, returning the tiles it passed through and the node for that splitter.
+    fn trace_segment(&self, mut beam: Beam) -> (Vec<Coord>, Option<usize>) {
+        let mut tiles = Vec::new();
+        let mut seen: HashSet<Beam> = HashSet::new();
+        while let Some(&tile) = self.map.get(beam.position.y, beam.position.x) {
+            if !seen.insert(beam) {
+                break;
+            }
+            let position = beam.position;
+            match tile {
+                '.' => beam.shift(),
+                '/' | '\\' => {
+                    beam.reflect(tile);
+                    beam.shift();
+                }
+                '|' | '-' => {
+                    // passing through end on, `handle_split` moves the beam along itself
+                    if beam.handle_split(tile).is_some() {
+                        return (tiles, Some(self.nodes[&position]));
                     }
-                    _ => unreachable!(),
                 }
-            } else {
-                removed_beams.insert(*beam);
+                _ => unreachable!(),
             }
+            tiles.push(position);
+        }
+        (tiles, None)
+    }
+
+    pub fn energized(&self, start: Beam) -> TileSet {
+        let mut tiles = TileSet::new(self.map.num_columns(), self.map.num_rows());
+        let (segment, hit) = self.trace_segment(start);
+        segment.iter().for_each(|&tile| tiles.insert(tile));
+        if let Some(node) = hit {
+            tiles.union_with(&self.component_tiles[self.component[node]]);
         }
+        tiles
+    }
+}
 
-        beams.retain(|x| !removed_beams.contains(x));
-        beams.extend(future_beams);
+/// Tarjan's algorithm. Components come out in reverse topological order, so every
+/// component is listed after all of the ones it has edges to.
+fn strongly_connected_components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State<'a> {
+        edges: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low_link: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
     }
 
-    energized_tiles.len() as isize
+    fn visit(state: &mut State, node: usize) {
+        state.index[node] = Some(state.next_index);
+        state.low_link[node] = state.next_index;
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+        for &next in state.edges[node].iter() {
+            match state.index[next] {
+                None => {
+                    visit(state, next);
+                    state.low_link[node] = state.low_link[node].min(state.low_link[next]);
+                }
+                Some(next_index) if state.on_stack[next] => {
+                    state.low_link[node] = state.low_link[node].min(next_index);
+                }
+                Some(_) => (),
+            }
+        }
+        if Some(state.low_link[node]) == state.index[node] {
+            let mut component = Vec::new();
+            loop {
+                let member = state.stack.pop().unwrap();
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        edges,
+        index: vec![None; edges.len()],
+        low_link: vec![0; edges.len()],
+        on_stack: vec![false; edges.len()],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            visit(&mut state, node);
+        }
+    }
+    state.components
 }
 
-fn part2(contents: String) -> isize {
-    let map = read_2d_map(contents);
-    let mut max_energized_tiles = 0;
-    for x in 0..map.num_columns() {
-        max_energized_tiles = max(
-            count_energized_tiles(
-                &map,
-                Beam {
-                    position: Coord { x, y: 0 },
-                    direction: Direction::South,
-                },
-            ),
-            max_energized_tiles,
-        );
-        max_energized_tiles = max(
-            count_energized_tiles(
-                &map,
-                Beam {
-                    position: Coord {
-                        x,
-                        y: map.num_rows() - 1,
-                    },
-                    direction: Direction::North,
-                },
-            ),
-            max_energized_tiles,
-        );
+/// Every beam that starts at the edge of the map pointing inwards.
+fn edge_starts(map: &Array2D<char>) -> Vec<Beam> {
+    let (width, height) = (map.num_columns(), map.num_rows());
+    let vertical = (0..width).flat_map(|x| {
+        [
+            (Coord::new(x, 0), Direction::South),
+            (Coord::new(x, height - 1), Direction::North),
+        ]
+    });
+    let horizontal = (0..height).flat_map(|y| {
+        [
+            (Coord::new(0, y), Direction::East),
+            (Coord::new(width - 1, y), Direction::West),
+        ]
+    });
+    vertical
+        .chain(horizontal)
+        .map(|(position, direction)| Beam {
+            position,
+            direction,
+        })
+        .collect()
+}
+
+/// Follows every beam one tile at a time, returning the directions beams were heading in
+/// as they entered each tile they lit.
+fn beam_directions(map: &Array2D<char>, start: Beam) -> HashMap<Coord, Vec<Direction>> {
+    let mut seen: HashSet<Beam> = HashSet::new();
+    let mut directions: HashMap<Coord, Vec<Direction>> = HashMap::new();
+    let mut beams = vec![start];
+    while let Some(mut beam) = beams.pop() {
+        let Some(&tile) = map.get(beam.position.y, beam.position.x) else {
+            continue;
+        };
+        if !seen.insert(beam) {
+            continue;
+        }
+        directions
+            .entry(beam.position)
+            .or_default()
+            .push(beam.direction);
+        match tile {
+            '.' => beam.shift(),
+            '/' | '\\' => {
+                beam.reflect(tile);
+                beam.shift();
+            }
+            _ => {
+                if let Some(mut other) = beam.handle_split(tile) {
+                    beam.shift();
+                    other.shift();
+                    beams.push(other);
+                }
+            }
+        }
+        beams.push(beam);
     }
-    for y in 0..map.num_rows() {
-        max_energized_tiles = max(
-            count_energized_tiles(
-                &map,
-                Beam {
-                    position: Coord { x: 0, y },
-                    direction: Direction::East,
-                },
-            ),
-            max_energized_tiles,
-        );
-        max_energized_tiles = max(
-            count_energized_tiles(
-                &map,
-                Beam {
-                    position: Coord {
-                        x: map.num_columns() - 1,
-                        y,
-                    },
-                    direction: Direction::West,
-                },
-            ),
-            max_energized_tiles,
-        );
+    directions
+}
+
+/// The map with energized tiles in yellow, where empty tiles show the way the beam through
+/// them was going, or how many beams crossed them if there were several. The start tile is
+/// on a red background.
+fn render(map: &Array2D<char>, start: Beam) -> Array2D<ColoredString> {
+    let directions = beam_directions(map, start);
+    let mut rendered = Array2D::filled_with(".".normal(), map.num_rows(), map.num_columns());
+    for ((y, x), &tile) in map.enumerate_row_major() {
+        let glyph = match directions.get(&Coord::new(x, y)) {
+            Some(beams) if tile == '.' && beams.len() == 1 => beams[0].arrow().to_string(),
+            Some(beams) if tile == '.' => beams.len().to_string(),
+            _ => tile.to_string(),
+        };
+        let glyph = if directions.contains_key(&Coord::new(x, y)) {
+            glyph.bold().yellow()
+        } else {
+            glyph.dimmed()
+        };
+        rendered[(y, x)] = if Coord::new(x, y) == start.position {
+            glyph.on_red()
+        } else {
+            glyph
+        };
     }
-    max_energized_tiles
+    rendered
+}
+
+/// The edge start that energizes the most tiles, and how many it energizes. Ties go to
+/// whichever comes first in `edge_starts`.
+fn best_start(map: &Array2D<char>) -> (Beam, usize) {
+    let graph = BeamGraph::new(map);
+    let starts = edge_starts(map);
+    let energized = parallel::map(&starts, |&start| graph.energized(start).len());
+    starts
+        .into_iter()
+        .zip(energized)
+        .rev()
+        .max_by_key(|&(_, energized)| energized)
+        .unwrap()
+}
+
+fn part1(contents: String) -> isize {
+    let map = read_2d_map(contents);
+    let start = Beam {
+        position: Coord::new(0, 0),
+        direction: Direction::East,
+    };
+    BeamGraph::new(&map).energized(start).len() as isize
+}
+
+/// Just the answer, for the sample tests. `main` calls `best_start` itself so it can print
+/// the winning start as well.
+#[cfg(test)]
+fn part2(contents: String) -> isize {
+    let map = read_2d_map(contents);
+    best_start(&map).1 as isize
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{part1, part2, read_input_file};
+    use std::collections::HashSet;
+
+    use aoc2023::{random::Xorshift, utils::read_2d_map};
+    use array2d::Array2D;
+    use colored::Color;
+
+    use crate::{
+        beam_directions, best_start, edge_starts, part1, part2, read_input_file, render,
+        strongly_connected_components, Beam, BeamGraph, Coord, Direction,
+    };
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
         pub expected_out: isize,
     }
+    /// Follows every beam one tile at a time for checking the other walkers against. It
+    /// handles directions and tiles itself rather than going through `Beam`, so a mistake
+    /// there can't hide by showing up on both sides.
+    fn simulate(map: &Array2D<char>, start: Beam) -> usize {
+        let direction = match start.direction {
+            Direction::North => (0, -1),
+            Direction::West => (-1, 0),
+            Direction::South => (0, 1),
+            Direction::East => (1, 0),
+        };
+        let position = (start.position.x as i64, start.position.y as i64);
+        let mut seen: HashSet<((i64, i64), (i64, i64))> = HashSet::new();
+        let mut beams = vec![(position, direction)];
+        while let Some(((x, y), (dx, dy))) = beams.pop() {
+            let Some(&tile) = map.get(y as usize, x as usize) else {
+                continue;
+            };
+            // off the top or left wraps round to a huge index, which `get` rejects too
+            if !seen.insert(((x, y), (dx, dy))) {
+                continue;
+            }
+            let directions = match tile {
+                '/' => vec![(-dy, -dx)],
+                '\\' => vec![(dy, dx)],
+                '|' if dx != 0 => vec![(0, -1), (0, 1)],
+                '-' if dy != 0 => vec![(-1, 0), (1, 0)],
+                _ => vec![(dx, dy)],
+            };
+            for (dx, dy) in directions {
+                beams.push(((x + dx, y + dy), (dx, dy)));
+            }
+        }
+        seen.iter()
+            .map(|&(position, _)| position)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
     impl Sample {
         pub fn run(&self) {
             let contents = read_input_file(file!(), self.input_file);
@@ -255,4 +575,132 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn graph_matches_simulation() {
+        let map = read_2d_map(read_input_file(file!(), "sample.txt"));
+        let graph = BeamGraph::new(&map);
+        for start in edge_starts(&map) {
+            assert_eq!(
+                graph.energized(start).len(),
+                simulate(&map, start),
+                "{:?}",
+                start
+            );
+        }
+    }
+
+    #[test]
+    fn graph_matches_simulation_on_generated_maps() {
+        // small maps crowded with mirrors and splitters, so there are plenty of loops and
+        // shared components
+        let mut random = Xorshift::new(0x853C49E6748FEA9B);
+        for _ in 0..20 {
+            let contents: String = (0..16)
+                .map(|_| {
+                    let row: String = (0..16)
+                        .map(|_| {
+                            ['.', '.', '.', '.', '/', '\\', '|', '-'][random.below(8) as usize]
+                        })
+                        .collect();
+                    row + "\n"
+                })
+                .collect();
+            let map = read_2d_map(contents.clone());
+            let graph = BeamGraph::new(&map);
+            for start in edge_starts(&map) {
+                let expected = simulate(&map, start);
+                assert_eq!(
+                    graph.energized(start).len(),
+                    expected,
+                    "{:?} on\n{}",
+                    start,
+                    contents
+                );
+                // the renderer has its own walker, so check that too
+                assert_eq!(
+                    beam_directions(&map, start).len(),
+                    expected,
+                    "{:?} on\n{}",
+                    start,
+                    contents
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn loops_without_splitters_hit_side_on() {
+        // the beam goes round the mirrors and through the splitter end on forever
+        let map = read_2d_map("/.\\.\n|...\n\\./.\n".to_string());
+        let graph = BeamGraph::new(&map);
+        for start in edge_starts(&map) {
+            assert_eq!(
+                graph.energized(start).len(),
+                simulate(&map, start),
+                "{:?}",
+                start
+            );
+        }
+    }
+
+    #[test]
+    fn components_come_out_in_reverse_topological_order() {
+        // 0 <-> 1 -> 2 -> 3 <-> 4, and 5 on its own
+        let edges = vec![vec![1], vec![0, 2], vec![3], vec![4], vec![3], vec![]];
+        let mut components = strongly_connected_components(&edges);
+        components.iter_mut().for_each(|x| x.sort_unstable());
+        assert_eq!(components, [vec![3, 4], vec![2], vec![0, 1], vec![5]]);
+    }
+
+    #[test]
+    fn render_sample() {
+        let map = read_2d_map(read_input_file(file!(), "sample.txt"));
+        let start = Beam {
+            position: Coord::new(0, 0),
+            direction: Direction::East,
+        };
+        let rendered = render(&map, start);
+        let lit = rendered
+            .elements_row_major_iter()
+            .filter(|x| x.fgcolor == Some(Color::Yellow))
+            .count();
+        assert_eq!(lit, 46);
+        assert_eq!(&*rendered[(0, 0)], ">");
+        assert_eq!(rendered[(0, 0)].bgcolor, Some(Color::Red));
+        assert_eq!(rendered[(1, 0)].bgcolor, None);
+        // splitters and mirrors keep their own glyph
+        assert_eq!(&*rendered[(0, 1)], "|");
+        assert_eq!(rendered[(0, 1)].fgcolor, Some(Color::Yellow));
+        // the bottom right corner is never reached
+        assert_eq!(&*rendered[(9, 9)], ".");
+        assert_eq!(rendered[(9, 9)].fgcolor, None);
+
+        let directions = beam_directions(&map, start);
+        let row_two: String = rendered.row_iter(2).unwrap().map(|x| &**x).collect();
+        assert_eq!(row_two, ".v...|->>>");
+        let crossed = directions
+            .iter()
+            .find(|(tile, beams)| map[(tile.y, tile.x)] == '.' && beams.len() > 1)
+            .unwrap();
+        assert_eq!(
+            &*rendered[(crossed.0.y, crossed.0.x)],
+            crossed.1.len().to_string()
+        );
+    }
+
+    #[test]
+    fn best_start_sample() {
+        let map = read_2d_map(read_input_file(file!(), "sample.txt"));
+        let (start, energized) = best_start(&map);
+        assert_eq!(energized, 51);
+        assert_eq!(
+            start,
+            Beam {
+                position: Coord::new(3, 0),
+                direction: Direction::South,
+            }
+        );
+        assert_eq!(start.to_string(), "at (3, 0) heading South");
+    }
 }
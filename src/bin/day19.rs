@@ -1,13 +1,98 @@
+#![cfg_attr(test, feature(test))]
+
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    env,
+    fmt::{self, Display},
+    fs,
     time::Instant,
 };
 
-use aoc2023::utils::read_input_file;
+use aoc2023::{random::Xorshift, utils::read_input_file};
+use itertools::Itertools;
 
 fn main() {
     let contents = read_input_file(file!(), "input.txt");
+    if env::args().any(|x| x == "--simplify") {
+        let system = configured_system(&contents);
+        print!("{}", system.analyze());
+        println!();
+        print!("{}", system.simplify());
+        return;
+    }
+    if env::args().any(|x| x == "--accepted") {
+        let system = configured_system(&contents);
+        for accepted in system.accepted_ranges() {
+            println!(
+                "{} via {}",
+                system.schema.describe(&accepted.range),
+                accepted.path.iter().join(" -> ")
+            );
+        }
+        return;
+    }
+    if env::args().any(|x| x == "--explain") {
+        let system = configured_system(&contents);
+        let items = contents.split("\n\n").nth(1).unwrap();
+        for item in items.split('\n').take_while(|x| !x.is_empty()) {
+            let part = MachinePart::from_str(item, &system.schema);
+            println!("{}: {}", item, system.trace_part(&part));
+        }
+        return;
+    }
+    if let Some(count) = env::args().find_map(|x| x.strip_prefix("--generate=").map(str::to_owned))
+    {
+        let system = configured_system(&contents);
+        for part in generate_parts(&system.schema, count.parse().unwrap()) {
+            println!("{}", system.schema.describe_part(&part));
+        }
+        return;
+    }
+    if let Some(path) = env::args().find_map(|x| x.strip_prefix("--classify=").map(str::to_owned)) {
+        let system = configured_system(&contents);
+        let parts: Vec<MachinePart> = fs::read_to_string(path)
+            .unwrap()
+            .split('\n')
+            .filter(|x| !x.is_empty())
+            .map(|x| MachinePart::from_str(x, &system.schema))
+            .collect();
+        let start = Instant::now();
+        let results = system.compile().classify_all(&parts);
+        let duration = start.elapsed();
+        let score: usize = parts
+            .iter()
+            .zip(results.iter())
+            .filter(|(_, &accepted)| accepted)
+            .map(|(part, _)| part.get_score())
+            .sum();
+        let accepted_count = results.iter().filter(|&&x| x).count();
+        println!("accepted {} of {} parts", accepted_count, parts.len());
+        println!("total score: {}", score);
+        println!("compiling and classifying took {:?}", duration);
+        return;
+    }
+    // a rough side-by-side timing on generated parts, `cargo bench --bin day19` measures properly
+    if let Some(count) = env::args().find_map(|x| x.strip_prefix("--time=").map(str::to_owned)) {
+        let system = configured_system(&contents);
+        let parts = generate_parts(&system.schema, count.parse().unwrap());
+        let start = Instant::now();
+        let interpreted: Vec<bool> = parts.iter().map(|part| system.check_part(part)).collect();
+        println!("interpreter took {:?}", start.elapsed());
+        let start = Instant::now();
+        let compiled = system.compile();
+        println!("compiling took {:?}", start.elapsed());
+        let start = Instant::now();
+        let results = compiled.classify_all(&parts);
+        println!("compiled system took {:?}", start.elapsed());
+        assert_eq!(interpreted, results);
+        return;
+    }
+    if env::args().any(|x| x == "--dot") {
+        let system = configured_system(&contents);
+        print!("{}", system.to_dot(env::args().any(|x| x == "--counts")));
+        return;
+    }
     let start = Instant::now();
     let part1 = part1(contents);
     let duration = start.elapsed();
@@ -21,30 +106,94 @@ fn main() {
     println!("part 2 took {:?}", duration2);
 }
 
-static ATTR_NAMES: [char; 4] = ['x', 'm', 'a', 's'];
-#[derive(Copy, Clone)]
-struct MachinePart {
-    attribute_values: [usize; 4],
+/// The attributes parts are rated on, in the order part descriptions list them, and the
+/// inclusive range of values each rating can take.
+#[derive(Debug, Clone)]
+struct RatingSchema {
+    names: Vec<String>,
+    bounds: Vec<(usize, usize)>,
 }
-impl MachinePart {
-    pub fn from_str(description: &str) -> Self {
-        let mut value_iter = description[1..description.len() - 1]
-            .split(',')
-            .map(|x| x[2..].parse::<usize>().unwrap());
 
-        MachinePart {
-            attribute_values: [
-                value_iter.next().unwrap(),
-                value_iter.next().unwrap(),
-                value_iter.next().unwrap(),
-                value_iter.next().unwrap(),
-            ],
+impl RatingSchema {
+    pub fn new(names: &[&str], start: usize, end: usize) -> Self {
+        RatingSchema {
+            names: names.iter().map(|x| x.to_string()).collect(),
+            bounds: vec![(start, end); names.len()],
         }
     }
 
-    pub fn get_attribute(&self, attribute: char) -> usize {
-        let attribute_index = ATTR_NAMES.iter().position(|&x| x == attribute).unwrap();
-        self.attribute_values[attribute_index]
+    /// Takes the attribute names from the first part in `contents`, adding any others used by
+    /// workflow conditions in the order they first appear. If `contents` doesn't list any
+    /// parts, only the attributes the conditions mention are known, so use `new` for a system
+    /// that should count over others too. Every rating is bounded to `1..=4000` like the
+    /// puzzle's, use `with_bounds` to change that.
+    pub fn infer(contents: &str) -> Self {
+        let (state_lines, items) = contents.split_once("\n\n").unwrap_or((contents, ""));
+        let mut names: Vec<&str> = Vec::new();
+        if let Some(first_item) = items.split('\n').find(|x| !x.is_empty()) {
+            for rating in first_item[1..first_item.len() - 1].split(',') {
+                names.push(rating.split('=').next().unwrap());
+            }
+        }
+        for line in state_lines.split('\n').take_while(|x| !x.is_empty()) {
+            let brace_position = line.find('{').unwrap();
+            for rule in line[brace_position + 1..line.len() - 1].split(',') {
+                if let Some((test, _)) = rule.split_once(':') {
+                    let (attribute, _, _) = parse_comparison(test);
+                    if !names.contains(&attribute) {
+                        names.push(attribute);
+                    }
+                }
+            }
+        }
+        RatingSchema::new(&names, 1, 4000)
+    }
+
+    pub fn with_bounds(mut self, attribute: &str, start: usize, end: usize) -> Self {
+        let attribute_index = self.index_of(attribute);
+        self.bounds[attribute_index] = (start, end);
+        self
+    }
+
+    fn index_of(&self, attribute: &str) -> usize {
+        self.names
+            .iter()
+            .position(|x| x == attribute)
+            .unwrap_or_else(|| panic!("attribute {} is not part of the schema", attribute))
+    }
+
+    /// Formats `part` the same way the puzzle input lists parts.
+    pub fn describe_part(&self, part: &MachinePart) -> String {
+        let ratings = self
+            .names
+            .iter()
+            .zip(part.attribute_values.iter())
+            .map(|(name, value)| format!("{}={}", name, value))
+            .join(",");
+        format!("{{{}}}", ratings)
+    }
+
+    pub fn describe(&self, range: &MachinePartRange) -> String {
+        self.names
+            .iter()
+            .zip(range.attribute_ranges.iter())
+            .map(|(name, (start, end))| format!("{}={}..={}", name, start, end))
+            .join(", ")
+    }
+}
+
+#[derive(Clone)]
+struct MachinePart {
+    attribute_values: Vec<usize>,
+}
+impl MachinePart {
+    pub fn from_str(description: &str, schema: &RatingSchema) -> Self {
+        let mut attribute_values = vec![0; schema.names.len()];
+        for rating in description[1..description.len() - 1].split(',') {
+            let (name, value) = rating.split_once('=').unwrap();
+            attribute_values[schema.index_of(name)] = value.parse().unwrap();
+        }
+        MachinePart { attribute_values }
     }
 
     pub fn get_score(&self) -> usize {
@@ -52,15 +201,15 @@ impl MachinePart {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct MachinePartRange {
-    attribute_ranges: [(usize, usize); 4],
+    attribute_ranges: Vec<(usize, usize)>,
 }
 
 impl MachinePartRange {
-    pub fn new() -> Self {
+    pub fn new(schema: &RatingSchema) -> Self {
         MachinePartRange {
-            attribute_ranges: [(1, 4000); 4],
+            attribute_ranges: schema.bounds.clone(),
         }
     }
 
@@ -75,114 +224,287 @@ impl MachinePartRange {
         self.size() == 0
     }
 
-    pub fn split_by(&self, condition: &Condition) -> (Self, Self) {
-        let attribute_index = ATTR_NAMES
-            .iter()
-            .position(|&x| x == condition.attribute)
-            .unwrap();
-        let (start, end) = self.attribute_ranges[attribute_index];
-        if condition.operation == '>' {
-            let new_start = max(start, condition.value + 1);
-            (
-                self.with(attribute_index, (new_start, end)),
-                self.with(attribute_index, (start, new_start - 1)),
-            )
-        } else {
-            let new_end = min(end, condition.value - 1);
-            (
-                self.with(attribute_index, (start, new_end)),
-                self.with(attribute_index, (new_end + 1, end)),
-            )
-        }
+    /// Splits into the parts that satisfy `condition` and the non-empty pieces that don't.
+    pub fn split_by(&self, condition: &Condition) -> (Self, Vec<Self>) {
+        let (matched, unmatched) = condition
+            .operation
+            .split_interval(self.attribute_ranges[condition.attribute], condition.value);
+        let unmatched = unmatched
+            .into_iter()
+            .filter(|(start, end)| start <= end)
+            .map(|range| self.with(condition.attribute, range))
+            .collect();
+        (self.with(condition.attribute, matched), unmatched)
     }
 
     fn with(&self, attribute_index: usize, new_range: (usize, usize)) -> MachinePartRange {
-        let mut res = *self;
+        let mut res = self.clone();
         res.attribute_ranges[attribute_index] = new_range;
         res
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Operation {
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Equal,
+    /// Used by the last rule of a workflow, which every part satisfies.
+    Always,
+}
+
+impl Operation {
+    fn from_symbol(symbol: &str) -> Self {
+        match symbol {
+            "<" => Operation::Less,
+            "<=" => Operation::LessEq,
+            ">" => Operation::Greater,
+            ">=" => Operation::GreaterEq,
+            "==" => Operation::Equal,
+            _ => panic!("invalid comparison operator {}", symbol),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Operation::Less => "<",
+            Operation::LessEq => "<=",
+            Operation::Greater => ">",
+            Operation::GreaterEq => ">=",
+            Operation::Equal => "==",
+            Operation::Always => "",
+        }
+    }
+
+    fn compare(&self, part_value: usize, value: usize) -> bool {
+        match self {
+            Operation::Less => part_value < value,
+            Operation::LessEq => part_value <= value,
+            Operation::Greater => part_value > value,
+            Operation::GreaterEq => part_value >= value,
+            Operation::Equal => part_value == value,
+            Operation::Always => true,
+        }
+    }
+
+    /// Splits the inclusive range `(start, end)` into the values that pass the comparison
+    /// against `value` and the ones that don't. Empty ranges have `start > end`.
+    fn split_interval(
+        &self,
+        (start, end): (usize, usize),
+        value: usize,
+    ) -> ((usize, usize), Vec<(usize, usize)>) {
+        let below = if value == 0 {
+            (1, 0)
+        } else {
+            (start, min(end, value - 1))
+        };
+        let up_to = (start, min(end, value));
+        let at = (max(start, value), min(end, value));
+        let from = (max(start, value), end);
+        let above = (max(start, value + 1), end);
+        match self {
+            Operation::Less => (below, vec![from]),
+            Operation::LessEq => (up_to, vec![above]),
+            Operation::Greater => (above, vec![up_to]),
+            Operation::GreaterEq => (from, vec![below]),
+            Operation::Equal => (at, vec![below, above]),
+            Operation::Always => ((start, end), vec![]),
+        }
+    }
+}
+
+/// Splits a test like `a<2006` or `hp>=10` into its attribute, operation and value.
+fn parse_comparison(test: &str) -> (&str, Operation, usize) {
+    let operation_index = test.find(['<', '>', '=']).unwrap();
+    let value_index = operation_index
+        + test[operation_index..]
+            .find(|x: char| x.is_ascii_digit())
+            .unwrap();
+    (
+        &test[..operation_index],
+        Operation::from_symbol(&test[operation_index..value_index]),
+        test[value_index..].parse().unwrap(),
+    )
+}
+
+#[derive(Debug, Clone)]
 struct Condition {
-    attribute: char,
-    operation: char,
+    attribute: usize,
+    attribute_name: String,
+    operation: Operation,
     value: usize,
     destination: String,
 }
 
 impl Condition {
-    pub fn from_str(description: &str) -> Self {
-        if let Some(operation_index) = description.find(|x| x == '<' || x == '>') {
-            let colon_index = description.find(':').unwrap();
-            let attribute = description.chars().next().unwrap();
-            let value: usize = description[operation_index + 1..colon_index]
-                .parse()
-                .unwrap();
-            let destination = &description[colon_index + 1..];
+    pub fn from_str(description: &str, schema: &RatingSchema) -> Self {
+        if let Some((test, destination)) = description.split_once(':') {
+            let (attribute_name, operation, value) = parse_comparison(test);
             Condition {
-                attribute,
-                operation: description.chars().nth(operation_index).unwrap(),
+                attribute: schema.index_of(attribute_name),
+                attribute_name: attribute_name.to_owned(),
+                operation,
                 value,
                 destination: destination.to_owned(),
             }
         } else {
-            Condition {
-                attribute: 'x',
-                operation: '>',
-                value: 0,
-                destination: description.to_owned(),
-            }
+            Condition::fallback(description.to_owned())
+        }
+    }
+
+    /// A condition that every part satisfies, used for the last rule of a workflow.
+    fn fallback(destination: String) -> Self {
+        Condition {
+            attribute: 0,
+            attribute_name: String::new(),
+            operation: Operation::Always,
+            value: 0,
+            destination,
+        }
+    }
+
+    fn is_fallback(&self) -> bool {
+        self.operation == Operation::Always
+    }
+
+    /// The comparison part of the rule, e.g. `a<2006`, or nothing for a fallback.
+    fn test_label(&self) -> String {
+        if self.is_fallback() {
+            return String::new();
         }
+        format!(
+            "{}{}{}",
+            self.attribute_name,
+            self.operation.symbol(),
+            self.value
+        )
     }
 
     fn check(&self, part: &MachinePart) -> Option<&str> {
-        let part_value = part.get_attribute(self.attribute);
-        if self.operation == '>' && part_value > self.value
-            || self.operation == '<' && part_value < self.value
-        {
+        let part_value = part.attribute_values[self.attribute];
+        if self.operation.compare(part_value, self.value) {
             return Some(&self.destination);
         }
         None
     }
 }
 
-#[derive(Debug)]
+impl Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_fallback() {
+            write!(f, "{}", self.destination)
+        } else {
+            write!(f, "{}:{}", self.test_label(), self.destination)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 struct Workflow {
     conditions: Vec<Condition>,
 }
 
 impl Workflow {
-    pub fn from_str(description: &str) -> Self {
-        let conditions: Vec<Condition> = description.split(',').map(Condition::from_str).collect();
+    pub fn from_str(description: &str, schema: &RatingSchema) -> Self {
+        let conditions: Vec<Condition> = description
+            .split(',')
+            .map(|x| Condition::from_str(x, schema))
+            .collect();
         Workflow { conditions }
     }
 
     fn process(&self, part: &MachinePart) -> &str {
-        for condition in self.conditions.iter() {
-            if let Some(destination) = condition.check(part) {
-                return destination;
+        &self.conditions[self.matching_rule(part)].destination
+    }
+
+    /// Index of the first rule whose condition `part` satisfies.
+    fn matching_rule(&self, part: &MachinePart) -> usize {
+        self.conditions
+            .iter()
+            .position(|condition| condition.check(part).is_some())
+            .unwrap()
+    }
+
+    /// Number of rules before the fallback.
+    fn conditional_count(&self) -> usize {
+        self.conditions
+            .iter()
+            .take_while(|x| !x.is_fallback())
+            .count()
+    }
+
+    /// Splits `range` into the non-empty pieces handled by each rule, paired with the index of
+    /// that rule.
+    fn split_range(&self, range: MachinePartRange) -> Vec<(usize, MachinePartRange)> {
+        let mut remaining = vec![range];
+        let mut res = Vec::new();
+        for (i, condition) in self.conditions.iter().enumerate() {
+            let mut next_remaining = Vec::new();
+            for current_range in remaining {
+                let (split_range, rest) = current_range.split_by(condition);
+                if !split_range.is_empty() {
+                    res.push((i, split_range));
+                }
+                next_remaining.extend(rest);
             }
+            remaining = next_remaining;
         }
-        unreachable!()
+        res
+    }
+
+    /// Removes rules at the end of the workflow that send parts to the same place as the
+    /// fallback, since they don't change where anything ends up. Returns whether anything was
+    /// removed.
+    fn trim_redundant(&mut self) -> bool {
+        let original_len = self.conditions.len();
+        while self.conditions.len() >= 2
+            && self.conditions[self.conditions.len() - 2].destination
+                == self.conditions[self.conditions.len() - 1].destination
+        {
+            self.conditions.remove(self.conditions.len() - 2);
+        }
+        self.conditions.len() != original_len
+    }
+}
+
+impl Display for Workflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.conditions.iter().join(","))
     }
 }
 
 struct ElfSortingSystem {
     workflows: HashMap<String, Workflow>,
+    schema: RatingSchema,
 }
 
 impl ElfSortingSystem {
-    pub fn from_str(state_lines: &str) -> Self {
+    /// Parses the workflows in `contents` using the rating schema inferred from it, which may
+    /// be the whole input or just the workflow lines. Without any parts, the schema only has
+    /// the attributes the workflows test, see `RatingSchema::infer`.
+    pub fn from_str(contents: &str) -> Self {
+        let state_lines = contents.split("\n\n").next().unwrap();
+        ElfSortingSystem::from_str_with_schema(state_lines, RatingSchema::infer(contents))
+    }
+
+    pub fn from_str_with_schema(state_lines: &str, schema: RatingSchema) -> Self {
         let mut workflows: HashMap<String, Workflow> =
             HashMap::with_capacity(state_lines.chars().filter(|&x| x == '\n').count());
         for line in state_lines.split('\n').take_while(|x| !x.is_empty()) {
             let brace_position = line.chars().position(|x| x == '{').unwrap();
             let key = &line[0..brace_position];
-            let value = Workflow::from_str(&line[brace_position + 1..line.len() - 1]);
+            let value = Workflow::from_str(&line[brace_position + 1..line.len() - 1], &schema);
             workflows.insert(key.to_owned(), value);
         }
-        ElfSortingSystem { workflows }
+        ElfSortingSystem { workflows, schema }
+    }
+
+    /// Every part allowed by the schema.
+    pub fn full_range(&self) -> MachinePartRange {
+        MachinePartRange::new(&self.schema)
     }
 
     pub fn check_part(&self, part: &MachinePart) -> bool {
@@ -194,6 +516,64 @@ impl ElfSortingSystem {
         current_workflow_name == "A"
     }
 
+    /// Follows `part` through the system like `check_part`, recording every rule it matches.
+    pub fn trace_part(&self, part: &MachinePart) -> PartTrace {
+        let mut steps: Vec<RuleStep> = Vec::new();
+        let mut current_workflow_name = "in";
+        while current_workflow_name != "R" && current_workflow_name != "A" {
+            let current_workflow = self.workflows.get(current_workflow_name).unwrap();
+            let rule_index = current_workflow.matching_rule(part);
+            steps.push(RuleStep::new(
+                current_workflow_name,
+                rule_index,
+                current_workflow,
+            ));
+            current_workflow_name = &current_workflow.conditions[rule_index].destination;
+        }
+        PartTrace {
+            steps,
+            accepted: current_workflow_name == "A",
+        }
+    }
+
+    /// Lists the disjoint boxes of parts that end up accepted, each with the rules that lead
+    /// there. Their sizes add up to `traverse_count("in", self.full_range())`.
+    pub fn accepted_ranges(&self) -> Vec<AcceptedRange> {
+        let mut accepted: Vec<AcceptedRange> = Vec::new();
+        self.collect_accepted("in", self.full_range(), &mut Vec::new(), &mut accepted);
+        accepted
+    }
+
+    fn collect_accepted(
+        &self,
+        workflow_name: &str,
+        range: MachinePartRange,
+        path: &mut Vec<RuleStep>,
+        accepted: &mut Vec<AcceptedRange>,
+    ) {
+        if range.is_empty() || workflow_name == "R" {
+            return;
+        }
+        if workflow_name == "A" {
+            accepted.push(AcceptedRange {
+                range,
+                path: path.clone(),
+            });
+            return;
+        }
+        let workflow = self.workflows.get(workflow_name).unwrap();
+        for (i, split_range) in workflow.split_range(range) {
+            path.push(RuleStep::new(workflow_name, i, workflow));
+            self.collect_accepted(
+                &workflow.conditions[i].destination,
+                split_range,
+                path,
+                accepted,
+            );
+            path.pop();
+        }
+    }
+
     pub fn traverse_count(&self, workflow_name: &str, range: MachinePartRange) -> usize {
         if range.is_empty() || workflow_name == "R" {
             return 0;
@@ -202,25 +582,513 @@ impl ElfSortingSystem {
             return range.size();
         }
         let workflow = self.workflows.get(workflow_name).unwrap();
-        let mut current_range = range;
-        let mut count = 0;
+        workflow
+            .split_range(range)
+            .into_iter()
+            .map(|(i, split_range)| {
+                self.traverse_count(&workflow.conditions[i].destination, split_range)
+            })
+            .sum()
+    }
+
+    /// Names of the workflows that can be reached from `start` by following rule destinations.
+    fn reachable_from(&self, start: &str) -> HashSet<String> {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut stack: Vec<&str> = vec![start];
+        while let Some(name) = stack.pop() {
+            let Some(workflow) = self.workflows.get(name) else {
+                continue;
+            };
+            if seen.insert(name.to_owned()) {
+                stack.extend(workflow.conditions.iter().map(|x| x.destination.as_str()));
+            }
+        }
+        seen
+    }
+
+    fn find_cycles(&self) -> Vec<Vec<String>> {
+        let mut finished: HashSet<&str> = HashSet::new();
+        let mut cycles: Vec<Vec<String>> = Vec::new();
+        for name in self.workflows.keys().sorted() {
+            self.cycle_search(name, &mut Vec::new(), &mut finished, &mut cycles);
+        }
+        cycles
+    }
+
+    fn cycle_search<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        finished: &mut HashSet<&'a str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if finished.contains(name) {
+            return;
+        }
+        if let Some(position) = path.iter().position(|&x| x == name) {
+            cycles.push(path[position..].iter().map(|x| x.to_string()).collect());
+            return;
+        }
+        let Some(workflow) = self.workflows.get(name) else {
+            return;
+        };
+        path.push(name);
         for condition in workflow.conditions.iter() {
-            let (split_range, new_range) = current_range.split_by(condition);
-            count += self.traverse_count(&condition.destination, split_range);
-            current_range = new_range;
+            self.cycle_search(&condition.destination, path, finished, cycles);
+        }
+        path.pop();
+        finished.insert(name);
+    }
+
+    /// Records every range of parts that arrives at each workflow when starting from `in`.
+    /// Only terminates if the system has no cycles.
+    fn collect_incoming(
+        &self,
+        workflow_name: &str,
+        range: MachinePartRange,
+        incoming: &mut HashMap<String, Vec<MachinePartRange>>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let Some(workflow) = self.workflows.get(workflow_name) else {
+            return;
+        };
+        incoming
+            .entry(workflow_name.to_owned())
+            .or_default()
+            .push(range.clone());
+        for (i, split_range) in workflow.split_range(range) {
+            self.collect_incoming(&workflow.conditions[i].destination, split_range, incoming);
+        }
+    }
+
+    pub fn analyze(&self) -> SystemReport {
+        let cycles = self.find_cycles();
+        let reachable = self.reachable_from("in");
+
+        // without cycles we know exactly which parts can arrive at each workflow, otherwise
+        // assume any part could show up at any workflow reachable from `in`
+        let incoming: HashMap<String, Vec<MachinePartRange>> = if cycles.is_empty() {
+            let mut incoming = HashMap::new();
+            self.collect_incoming("in", self.full_range(), &mut incoming);
+            incoming
+        } else {
+            reachable
+                .iter()
+                .map(|name| (name.clone(), vec![self.full_range()]))
+                .collect()
+        };
+
+        let mut report = SystemReport {
+            cycles,
+            ..Default::default()
+        };
+        for (name, workflow) in self.workflows.iter().sorted_by_key(|(name, _)| *name) {
+            for condition in workflow.conditions.iter() {
+                let destination = &condition.destination;
+                if destination != "A"
+                    && destination != "R"
+                    && !self.workflows.contains_key(destination)
+                {
+                    report.undefined.push((name.clone(), destination.clone()));
+                }
+            }
+            if workflow
+                .conditions
+                .iter()
+                .map(|x| &x.destination)
+                .all_equal()
+            {
+                report
+                    .uniform
+                    .push((name.clone(), workflow.conditions[0].destination.clone()));
+            }
+
+            let Some(ranges) = incoming.get(name) else {
+                report.unreachable.push(name.clone());
+                continue;
+            };
+            let live_rules: HashSet<usize> = ranges
+                .iter()
+                .flat_map(|range| workflow.split_range(range.clone()))
+                .map(|(i, _)| i)
+                .collect();
+            for i in 0..workflow.conditions.len() {
+                if !live_rules.contains(&i) {
+                    report.dead_rules.push((name.clone(), i));
+                }
+            }
+        }
+        report
+    }
+
+    /// Builds an equivalent system (for parts within the schema's bounds) with dead rules,
+    /// redundant rules, pass-through workflows and unreachable workflows removed.
+    pub fn simplify(&self) -> ElfSortingSystem {
+        let report = self.analyze();
+        let mut workflows: HashMap<String, Workflow> = HashMap::new();
+        for (name, workflow) in self.workflows.iter() {
+            if report.unreachable.contains(name) {
+                continue;
+            }
+            let mut conditions: Vec<Condition> = workflow
+                .conditions
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !report.dead_rules.contains(&(name.clone(), *i)))
+                .map(|(_, condition)| condition.clone())
+                .collect();
+            // everything after the last live rule was dead, so nothing is left over for it
+            if let Some(last) = conditions.last_mut() {
+                *last = Condition::fallback(last.destination.clone());
+            }
+            workflows.insert(name.clone(), Workflow { conditions });
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for workflow in workflows.values_mut() {
+                changed |= workflow.trim_redundant();
+            }
+            let aliases: HashMap<String, String> = workflows
+                .iter()
+                .filter(|(name, workflow)| *name != "in" && workflow.conditions.len() == 1)
+                .map(|(name, workflow)| (name.clone(), workflow.conditions[0].destination.clone()))
+                .collect();
+            for workflow in workflows.values_mut() {
+                for condition in workflow.conditions.iter_mut() {
+                    let mut seen: HashSet<&str> = HashSet::new();
+                    let mut target = condition.destination.as_str();
+                    while let Some(next) = aliases.get(target) {
+                        if !seen.insert(target) {
+                            // aliases loop back on themselves, leave it for the cycle report
+                            break;
+                        }
+                        target = next;
+                    }
+                    if !aliases.contains_key(target) && target != condition.destination {
+                        condition.destination = target.to_owned();
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut simplified = ElfSortingSystem {
+            workflows,
+            schema: self.schema.clone(),
+        };
+        let reachable = simplified.reachable_from("in");
+        simplified
+            .workflows
+            .retain(|name, _| reachable.contains(name));
+        simplified
+    }
+
+    /// Counts how many part combinations leave `workflow_name` through each of its rules,
+    /// keyed by (workflow, rule index). Only terminates if the system has no cycles.
+    fn collect_edge_flows(
+        &self,
+        workflow_name: &str,
+        range: MachinePartRange,
+        flows: &mut HashMap<(String, usize), usize>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let Some(workflow) = self.workflows.get(workflow_name) else {
+            return;
+        };
+        for (i, split_range) in workflow.split_range(range) {
+            *flows.entry((workflow_name.to_owned(), i)).or_default() += split_range.size();
+            self.collect_edge_flows(&workflow.conditions[i].destination, split_range, flows);
+        }
+    }
+
+    /// Renders the system as a Graphviz digraph. With `annotate_counts`, every edge is also
+    /// labelled with how many part combinations flow along it, unless the system has cycles.
+    pub fn to_dot(&self, annotate_counts: bool) -> String {
+        let mut flows: HashMap<(String, usize), usize> = HashMap::new();
+        let annotate_counts = annotate_counts && self.find_cycles().is_empty();
+        if annotate_counts {
+            self.collect_edge_flows("in", self.full_range(), &mut flows);
+        }
+
+        let mut lines: Vec<String> = vec![
+            "digraph workflows {".to_owned(),
+            "    \"A\" [shape=doublecircle, color=green];".to_owned(),
+            "    \"R\" [shape=doublecircle, color=red];".to_owned(),
+        ];
+        for (name, workflow) in self
+            .workflows
+            .iter()
+            .sorted_by_key(|(name, _)| (*name != "in", *name))
+        {
+            let shape = if name == "in" { "house" } else { "box" };
+            lines.push(format!("    \"{}\" [shape={}];", name, shape));
+            for (i, condition) in workflow.conditions.iter().enumerate() {
+                let mut label = condition.test_label();
+                if annotate_counts {
+                    let flow = flows.get(&(name.clone(), i)).copied().unwrap_or(0);
+                    if !label.is_empty() {
+                        label.push_str("\\n");
+                    }
+                    label.push_str(&flow.to_string());
+                }
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                    name, condition.destination, label
+                ));
+            }
+        }
+        lines.push("}".to_owned());
+        lines.join("\n") + "\n"
+    }
+
+    /// Flattens the system into a decision tree with workflow names interned into node
+    /// indices, so classifying a part doesn't need any string lookups.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a rule refers to an undefined workflow or workflows pass parts to each other
+    /// in a loop without testing anything.
+    pub fn compile(&self) -> CompiledSystem {
+        // every conditional rule becomes a node, fallbacks just become the `on_fail` of the
+        // rule before them. workflows are numbered in name order so the same system always
+        // compiles to the same nodes
+        let mut first_nodes: HashMap<&str, usize> = HashMap::new();
+        let mut node_count = 0;
+        for (name, workflow) in self.workflows.iter().sorted_by_key(|(name, _)| *name) {
+            let conditional_count = workflow.conditional_count();
+            if conditional_count > 0 {
+                first_nodes.insert(name, node_count);
+                node_count += conditional_count;
+            }
+        }
+
+        let mut nodes: Vec<DecisionNode> = Vec::with_capacity(node_count);
+        for (name, first_node) in first_nodes.iter().sorted_by_key(|(_, &x)| x) {
+            let workflow = self.workflows.get(*name).unwrap();
+            let conditional_count = workflow.conditional_count();
+            for (i, condition) in workflow.conditions[..conditional_count].iter().enumerate() {
+                let on_fail = if i + 1 < conditional_count {
+                    Decision::Node(first_node + i + 1)
+                } else {
+                    let fallback = &workflow.conditions[conditional_count];
+                    self.resolve_decision(&fallback.destination, &first_nodes)
+                };
+                nodes.push(DecisionNode {
+                    attribute: condition.attribute,
+                    operation: condition.operation,
+                    value: condition.value,
+                    on_match: self.resolve_decision(&condition.destination, &first_nodes),
+                    on_fail,
+                });
+            }
+        }
+        CompiledSystem {
+            nodes,
+            start: self.resolve_decision("in", &first_nodes),
+        }
+    }
+
+    /// Finds where parts sent to `workflow_name` end up in the compiled tree, skipping over
+    /// workflows that send everything to the same place.
+    fn resolve_decision(
+        &self,
+        workflow_name: &str,
+        first_nodes: &HashMap<&str, usize>,
+    ) -> Decision {
+        let mut current_workflow_name = workflow_name;
+        let mut seen: HashSet<&str> = HashSet::new();
+        loop {
+            match current_workflow_name {
+                "A" => return Decision::Accept,
+                "R" => return Decision::Reject,
+                _ => {}
+            }
+            if let Some(&node) = first_nodes.get(current_workflow_name) {
+                return Decision::Node(node);
+            }
+            if !seen.insert(current_workflow_name) {
+                panic!("workflow {} loops back on itself", current_workflow_name);
+            }
+            let workflow = self
+                .workflows
+                .get(current_workflow_name)
+                .unwrap_or_else(|| panic!("undefined workflow {}", current_workflow_name));
+            current_workflow_name = &workflow.conditions[0].destination;
+        }
+    }
+}
+
+impl Display for ElfSortingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, workflow) in self
+            .workflows
+            .iter()
+            .sorted_by_key(|(name, _)| (*name != "in", *name))
+        {
+            writeln!(f, "{}{{{}}}", name, workflow)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Decision {
+    Node(usize),
+    Accept,
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DecisionNode {
+    attribute: usize,
+    operation: Operation,
+    value: usize,
+    on_match: Decision,
+    on_fail: Decision,
+}
+
+/// An `ElfSortingSystem` flattened by `ElfSortingSystem::compile`.
+#[derive(Debug, PartialEq, Eq)]
+struct CompiledSystem {
+    nodes: Vec<DecisionNode>,
+    start: Decision,
+}
+
+impl CompiledSystem {
+    pub fn check_part(&self, part: &MachinePart) -> bool {
+        let mut current = self.start;
+        while let Decision::Node(index) = current {
+            let node = &self.nodes[index];
+            current = if node
+                .operation
+                .compare(part.attribute_values[node.attribute], node.value)
+            {
+                node.on_match
+            } else {
+                node.on_fail
+            };
+        }
+        current == Decision::Accept
+    }
+
+    pub fn classify_all(&self, parts: &[MachinePart]) -> Vec<bool> {
+        parts.iter().map(|part| self.check_part(part)).collect()
+    }
+}
+
+/// Makes `count` parts with random ratings inside the bounds of `schema`. Uses a fixed seed
+/// so runs are repeatable.
+fn generate_parts(schema: &RatingSchema, count: usize) -> Vec<MachinePart> {
+    let mut random = Xorshift::new(0x2545F4914F6CDD1D);
+    (0..count)
+        .map(|_| MachinePart {
+            attribute_values: schema
+                .bounds
+                .iter()
+                .map(|(start, end)| start + random.below((end - start + 1) as u64) as usize)
+                .collect(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RuleStep {
+    workflow: String,
+    rule_index: usize,
+    rule: String,
+}
+
+impl RuleStep {
+    fn new(workflow_name: &str, rule_index: usize, workflow: &Workflow) -> Self {
+        RuleStep {
+            workflow: workflow_name.to_owned(),
+            rule_index,
+            rule: workflow.conditions[rule_index].to_string(),
         }
-        count
     }
 }
 
+impl Display for RuleStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.workflow, self.rule)
+    }
+}
+
+#[derive(Debug)]
+struct PartTrace {
+    steps: Vec<RuleStep>,
+    accepted: bool,
+}
+
+impl Display for PartTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = if self.accepted { "A" } else { "R" };
+        write!(f, "{} -> {}", self.steps.iter().join(" -> "), result)
+    }
+}
+
+#[derive(Debug)]
+struct AcceptedRange {
+    range: MachinePartRange,
+    path: Vec<RuleStep>,
+}
+
+#[derive(Debug, Default)]
+struct SystemReport {
+    unreachable: Vec<String>,
+    /// (workflow, missing destination) pairs
+    undefined: Vec<(String, String)>,
+    cycles: Vec<Vec<String>>,
+    /// (workflow, rule index) pairs for rules no part can ever match
+    dead_rules: Vec<(String, usize)>,
+    /// workflows that send every part to the same destination
+    uniform: Vec<(String, String)>,
+}
+
+impl Display for SystemReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "unreachable workflows: {}", self.unreachable.join(", "))?;
+        for (name, destination) in self.undefined.iter() {
+            writeln!(f, "{} refers to undefined workflow {}", name, destination)?;
+        }
+        for cycle in self.cycles.iter() {
+            writeln!(f, "cycle: {} -> {}", cycle.join(" -> "), cycle[0])?;
+        }
+        for (name, index) in self.dead_rules.iter() {
+            writeln!(f, "rule {} of {} can never fire", index, name)?;
+        }
+        for (name, destination) in self.uniform.iter() {
+            writeln!(f, "{} always sends parts to {}", name, destination)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the system for the inspection modes in `main`, applying any
+/// `--bounds=<attribute>=<start>..=<end>` arguments to the inferred schema.
+fn configured_system(contents: &str) -> ElfSortingSystem {
+    let state_descriptions = contents.split("\n\n").next().unwrap();
+    let mut schema = RatingSchema::infer(contents);
+    for bound in env::args().filter_map(|x| x.strip_prefix("--bounds=").map(str::to_owned)) {
+        let (attribute, range) = bound.split_once('=').unwrap();
+        let (start, end) = range.split_once("..=").unwrap();
+        schema = schema.with_bounds(attribute, start.parse().unwrap(), end.parse().unwrap());
+    }
+    ElfSortingSystem::from_str_with_schema(state_descriptions, schema)
+}
+
 fn part1(contents: String) -> usize {
-    let mut file_iter = contents.split("\n\n");
-    let state_descriptions = file_iter.next().unwrap();
-    let items = file_iter.next().unwrap();
-    let system = ElfSortingSystem::from_str(state_descriptions);
+    let system = ElfSortingSystem::from_str(&contents);
+    let items = contents.split("\n\n").nth(1).unwrap();
     let mut total_score = 0;
     for item in items.split('\n').take_while(|x| !x.is_empty()) {
-        let part = MachinePart::from_str(item);
+        let part = MachinePart::from_str(item, &system.schema);
         if system.check_part(&part) {
             total_score += part.get_score();
         }
@@ -229,16 +1097,23 @@ fn part1(contents: String) -> usize {
 }
 
 fn part2(contents: String) -> usize {
-    let state_descriptions = contents.split("\n\n").next().unwrap();
-    let system = ElfSortingSystem::from_str(state_descriptions);
-    system.traverse_count("in", MachinePartRange::new())
+    let system = ElfSortingSystem::from_str(&contents);
+    system.traverse_count("in", system.full_range())
 }
 
 #[cfg(test)]
 mod tests {
+    extern crate test;
+
     use std::time::Instant;
 
-    use crate::{part1, part2, read_input_file};
+    use itertools::Itertools;
+    use test::Bencher;
+
+    use crate::{
+        generate_parts, part1, part2, read_input_file, Decision, ElfSortingSystem, MachinePart,
+        RatingSchema,
+    };
     struct Sample {
         pub input_file: &'static str,
         pub part_num: u8,
@@ -278,4 +1153,202 @@ mod tests {
         }
         .run()
     }
+
+    #[test]
+    fn simplified_sample_is_equivalent() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let (states, items) = contents.split_once("\n\n").unwrap();
+        let system = ElfSortingSystem::from_str(states);
+        let simplified = system.simplify();
+        assert!(simplified.workflows.len() < system.workflows.len());
+        assert!(!simplified.workflows.contains_key("gd"));
+        assert_eq!(
+            simplified.traverse_count("in", simplified.full_range()),
+            system.traverse_count("in", system.full_range())
+        );
+        for item in items.split('\n').take_while(|x| !x.is_empty()) {
+            let part = MachinePart::from_str(item, &system.schema);
+            assert_eq!(simplified.check_part(&part), system.check_part(&part));
+        }
+    }
+
+    #[test]
+    fn analyze_finds_dead_rules_and_unreachable_workflows() {
+        let system = ElfSortingSystem::from_str(
+            "in{a<10:foo,a<5:bar,A}\nfoo{x>5:R,R}\nbar{m>100:A,R}\norphan{m<5:missing,A}\n",
+        );
+        let report = system.analyze();
+        assert!(report.cycles.is_empty());
+        assert_eq!(report.dead_rules, vec![("in".to_string(), 1)]);
+        assert_eq!(report.unreachable, vec!["bar", "orphan"]);
+        assert_eq!(
+            report.undefined,
+            vec![("orphan".to_string(), "missing".to_string())]
+        );
+        assert_eq!(report.uniform, vec![("foo".to_string(), "R".to_string())]);
+        assert_eq!(system.simplify().to_string(), "in{a<10:R,A}\n");
+    }
+
+    #[test]
+    fn accepted_ranges_are_disjoint_and_cover_count() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let system = ElfSortingSystem::from_str(&contents);
+        let accepted = system.accepted_ranges();
+        let total: usize = accepted.iter().map(|x| x.range.size()).sum();
+        assert_eq!(total, 167409079868000);
+        for (first, second) in accepted.iter().tuple_combinations() {
+            let overlaps = first
+                .range
+                .attribute_ranges
+                .iter()
+                .zip(second.range.attribute_ranges.iter())
+                .all(|((s1, e1), (s2, e2))| s1.max(s2) <= e1.min(e2));
+            assert!(!overlaps, "{:?} overlaps {:?}", first, second);
+        }
+        assert!(accepted
+            .iter()
+            .all(|x| x.path.first().unwrap().workflow == "in"));
+    }
+
+    #[test]
+    fn trace_part_explains_decision() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let system = ElfSortingSystem::from_str(&contents);
+        let part = MachinePart::from_str("{x=787,m=2655,a=1222,s=2876}", &system.schema);
+        let trace = system.trace_part(&part);
+        assert!(trace.accepted);
+        assert_eq!(
+            trace
+                .steps
+                .iter()
+                .map(|x| x.workflow.as_str())
+                .collect::<Vec<_>>(),
+            vec!["in", "qqz", "qs", "lnx"]
+        );
+        assert_eq!(
+            trace.to_string(),
+            "in[qqz] -> qqz[s>2770:qs] -> qs[lnx] -> lnx[m>1548:A] -> A"
+        );
+        let part = MachinePart::from_str("{x=1679,m=44,a=2067,s=496}", &system.schema);
+        let trace = system.trace_part(&part);
+        assert!(!trace.accepted);
+    }
+
+    #[test]
+    fn dot_output_counts_edges() {
+        let system = ElfSortingSystem::from_str_with_schema(
+            "in{a<2006:foo,R}\nfoo{m>4000:R,A}\n",
+            RatingSchema::new(&["x", "m", "a", "s"], 1, 4000),
+        );
+        let dot = system.to_dot(true);
+        assert!(dot.starts_with("digraph workflows {\n"));
+        assert!(dot.contains(&format!(
+            "\"in\" -> \"foo\" [label=\"a<2006\\n{}\"];",
+            2005 * 4000_usize.pow(3)
+        )));
+        assert!(dot.contains("\"foo\" -> \"R\" [label=\"m>4000\\n0\"];"));
+        assert!(system
+            .to_dot(false)
+            .contains("\"foo\" -> \"A\" [label=\"\"];"));
+    }
+
+    #[test]
+    fn analyze_finds_cycles() {
+        let system =
+            ElfSortingSystem::from_str("in{x>1:loop1,A}\nloop1{m<5:loop2,R}\nloop2{loop1}\n");
+        let report = system.analyze();
+        assert_eq!(report.cycles, vec![vec!["loop1", "loop2"]]);
+        assert!(report.unreachable.is_empty());
+    }
+
+    #[test]
+    fn custom_schema_and_operators() {
+        let contents = "in{hp>=10:big,hp==5:A,R}\nbig{atk<=3:A,R}\n\n{hp=12,atk=2}\n{hp=5,atk=9}\n{hp=3,atk=1}\n";
+        let schema = RatingSchema::infer(contents);
+        assert_eq!(schema.names, vec!["hp", "atk"]);
+        // without any parts to go on, only the tested attributes are known
+        let schema_without_parts = RatingSchema::infer(contents.split("\n\n").next().unwrap());
+        assert_eq!(schema_without_parts.names, vec!["hp", "atk"]);
+        assert_eq!(part1(contents.to_string()), 28);
+
+        let schema = schema.with_bounds("hp", 1, 20).with_bounds("atk", 1, 20);
+        let system =
+            ElfSortingSystem::from_str_with_schema(contents.split("\n\n").next().unwrap(), schema);
+        assert_eq!(
+            system.traverse_count("in", system.full_range()),
+            11 * 3 + 20
+        );
+    }
+
+    #[test]
+    fn range_count_matches_brute_force() {
+        let schema = RatingSchema::new(&["p", "q"], 0, 12);
+        let system = ElfSortingSystem::from_str_with_schema(
+            "in{p==0:A,p<4:lo,q>=7:hi,R}\nlo{q<=3:R,q==5:R,p>2:A,q>10:A,R}\nhi{p>=9:A,q==7:A,p<=5:R,A}\n",
+            schema.clone(),
+        );
+        let brute_force = (0..=12)
+            .cartesian_product(0..=12)
+            .filter(|&(p, q)| {
+                let part = MachinePart::from_str(&format!("{{p={},q={}}}", p, q), &schema);
+                system.check_part(&part)
+            })
+            .count();
+        assert_eq!(
+            system.traverse_count("in", system.full_range()),
+            brute_force
+        );
+        let total: usize = system
+            .accepted_ranges()
+            .iter()
+            .map(|x| x.range.size())
+            .sum();
+        assert_eq!(total, brute_force);
+    }
+
+    #[test]
+    fn compiled_system_matches_interpreter() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let system = ElfSortingSystem::from_str(&contents);
+        let compiled = system.compile();
+        let parts = generate_parts(&system.schema, 10000);
+        let interpreted: Vec<bool> = parts.iter().map(|part| system.check_part(part)).collect();
+        assert_eq!(compiled.classify_all(&parts), interpreted);
+        assert!(interpreted.iter().any(|&x| x) && interpreted.iter().any(|&x| !x));
+
+        let system =
+            ElfSortingSystem::from_str("in{pass}\npass{a>5:also,R}\nalso{pass2}\npass2{A}\n");
+        let compiled = system.compile();
+        assert_eq!(compiled.nodes.len(), 1);
+        assert_eq!(compiled.nodes[0].on_match, Decision::Accept);
+    }
+
+    #[test]
+    fn compiled_nodes_follow_workflow_names() {
+        let contents = read_input_file(file!(), "sample.txt");
+        let compiled = ElfSortingSystem::from_str(&contents).compile();
+        // every parse gets its own hash order, which shouldn't leak into the numbering
+        for _ in 0..20 {
+            assert_eq!(ElfSortingSystem::from_str(&contents).compile(), compiled);
+        }
+        // `crn` is first alphabetically and has one conditional rule
+        assert_eq!(compiled.nodes[0].value, 2662);
+    }
+
+    #[bench]
+    fn bench_interpreter(b: &mut Bencher) {
+        let contents = read_input_file(file!(), "sample.txt");
+        let system = ElfSortingSystem::from_str(&contents);
+        let parts = generate_parts(&system.schema, 10000);
+        b.iter(|| parts.iter().filter(|part| system.check_part(part)).count());
+    }
+
+    #[bench]
+    fn bench_compiled(b: &mut Bencher) {
+        let contents = read_input_file(file!(), "sample.txt");
+        let system = ElfSortingSystem::from_str(&contents);
+        let parts = generate_parts(&system.schema, 10000);
+        let compiled = system.compile();
+        b.iter(|| compiled.classify_all(&parts));
+    }
 }
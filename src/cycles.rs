@@ -0,0 +1,142 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// Finds where repeatedly applying `step` to `start` starts going round in circles, as
+/// `(prefix, period)`: the state after `prefix` steps is the first one that comes back,
+/// and it comes back every `period` steps.
+///
+/// Uses Brent's algorithm, so it only ever holds a couple of states at once, at the cost of
+/// stepping through the sequence more than once. Never returns if the states don't repeat.
+pub fn find_cycle<T: Clone + PartialEq>(
+    start: &T,
+    mut step: impl FnMut(&T) -> T,
+) -> (usize, usize) {
+    // find the period by having the tortoise teleport to the hare at every power of two
+    let mut power = 1;
+    let mut period = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == period {
+            tortoise = hare.clone();
+            power *= 2;
+            period = 0;
+        }
+        hare = step(&hare);
+        period += 1;
+    }
+
+    // then walk two states a period apart from the start until they meet
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..period {
+        hare = step(&hare);
+    }
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    (prefix, period)
+}
+
+/// The same as `find_cycle`, but remembers every state it has seen so that it only steps
+/// through the sequence once.
+pub fn find_cycle_hashed<T: Clone + Eq + Hash>(
+    start: &T,
+    step: impl FnMut(&T) -> T,
+) -> (usize, usize) {
+    let (prefix, period, _) = walk_until_repeat(start, step, usize::MAX);
+    (prefix, period)
+}
+
+/// The state after applying `step` to `start` `n` times, skipping over however many times the
+/// states go round a cycle.
+pub fn nth_state<T: Clone + Eq + Hash>(start: &T, n: usize, step: impl FnMut(&T) -> T) -> T {
+    let (prefix, period, mut states) = walk_until_repeat(start, step, n);
+    let index = if n < states.len() {
+        n
+    } else {
+        prefix + (n - prefix) % period
+    };
+    states.swap_remove(index)
+}
+
+/// Steps from `start` until a state repeats or `limit` steps are done, whichever is first,
+/// returning the cycle found (zero if it stopped at the limit) and every state along the way.
+fn walk_until_repeat<T: Clone + Eq + Hash>(
+    start: &T,
+    mut step: impl FnMut(&T) -> T,
+    limit: usize,
+) -> (usize, usize, Vec<T>) {
+    let mut seen: HashMap<T, usize> = HashMap::new();
+    let mut states = vec![start.clone()];
+    while states.len() <= limit {
+        let state = states.last().unwrap();
+        if let Some(&prefix) = seen.get(state) {
+            states.pop();
+            return (prefix, states.len() - prefix, states);
+        }
+        seen.insert(state.clone(), states.len() - 1);
+        let next = step(state);
+        states.push(next);
+    }
+    (0, 0, states)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_cycle, find_cycle_hashed, nth_state};
+
+    /// A sequence that wanders for a while before settling into a loop, given how many
+    /// states lead into the loop and how long it is.
+    fn rho(prefix: usize, period: usize) -> impl Fn(&usize) -> usize {
+        move |&x| {
+            if x + 1 < prefix + period {
+                x + 1
+            } else {
+                prefix
+            }
+        }
+    }
+
+    #[test]
+    fn finds_known_cycles() {
+        for prefix in 0..20 {
+            for period in 1..20 {
+                assert_eq!(find_cycle(&0, rho(prefix, period)), (prefix, period));
+                assert_eq!(find_cycle_hashed(&0, rho(prefix, period)), (prefix, period));
+            }
+        }
+    }
+
+    #[test]
+    fn variants_agree() {
+        for seed in 0..200_u64 {
+            let step = |&x: &u64| (x * x + 7) % 1009;
+            assert_eq!(find_cycle(&seed, step), find_cycle_hashed(&seed, step));
+        }
+    }
+
+    #[test]
+    fn nth_state_matches_stepping() {
+        let step = |&x: &u64| (x * x + 7) % 1009;
+        for n in 0..3000 {
+            let mut state = 3;
+            for _ in 0..n {
+                state = step(&state);
+            }
+            assert_eq!(nth_state(&3, n, step), state);
+        }
+        assert_eq!(
+            nth_state(&0, 1_000_000_000_000, rho(5, 7)),
+            5 + (1_000_000_000_000 - 5) % 7
+        );
+    }
+
+    #[test]
+    fn nth_state_before_any_repeat() {
+        // stepping stops at n, so a sequence that never repeats is fine
+        assert_eq!(nth_state(&0_u64, 1000, |x| x + 1), 1000);
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    cmp::{max, min},
+    fmt::{self, Display},
+};
+
+/// A half-open range of integers `[start, end)`. Intervals with `start >= end` are empty.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Self {
+        Interval { start, end }
+    }
+
+    pub fn from_len(start: i64, length: i64) -> Self {
+        Interval {
+            start,
+            end: start + length,
+        }
+    }
+
+    pub fn len(&self) -> i64 {
+        max(self.end - self.start, 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.start <= value && value < self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Interval {
+        Interval {
+            start: max(self.start, other.start),
+            end: min(self.end, other.end),
+        }
+    }
+
+    pub fn shifted(&self, offset: i64) -> Interval {
+        Interval {
+            start: self.start + offset,
+            end: self.end + offset,
+        }
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {})", self.start, self.end)
+    }
+}
+
+/// A set of integers stored as sorted, disjoint, non-touching intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        IntervalSet::default()
+    }
+
+    pub fn from_intervals(intervals: impl IntoIterator<Item = Interval>) -> Self {
+        let mut intervals: Vec<Interval> =
+            intervals.into_iter().filter(|x| !x.is_empty()).collect();
+        intervals.sort_unstable();
+        let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if interval.start <= last.end => last.end = max(last.end, interval.end),
+                _ => merged.push(interval),
+            }
+        }
+        IntervalSet { intervals: merged }
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Number of integers in the set.
+    pub fn len(&self) -> i64 {
+        self.intervals.iter().map(|x| x.len()).sum()
+    }
+
+    pub fn min(&self) -> Option<i64> {
+        self.intervals.first().map(|x| x.start)
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let index = self.intervals.partition_point(|x| x.end <= value);
+        self.intervals.get(index).is_some_and(|x| x.contains(value))
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        *self = self.union(&IntervalSet::from_intervals([interval]));
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        IntervalSet::from_intervals(self.intervals.iter().chain(other.intervals.iter()).copied())
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut res = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let overlap = self.intervals[i].intersection(&other.intervals[j]);
+            if !overlap.is_empty() {
+                res.push(overlap);
+            }
+            // whichever interval ends first can't overlap anything else in the other set
+            if self.intervals[i].end < other.intervals[j].end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals: res }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut res = Vec::new();
+        let mut j = 0;
+        for interval in self.intervals.iter() {
+            let mut current_start = interval.start;
+            while j < other.intervals.len() && other.intervals[j].end <= current_start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.intervals.len() && other.intervals[k].start < interval.end {
+                if other.intervals[k].start > current_start {
+                    res.push(Interval::new(current_start, other.intervals[k].start));
+                }
+                current_start = max(current_start, other.intervals[k].end);
+                k += 1;
+            }
+            if current_start < interval.end {
+                res.push(Interval::new(current_start, interval.end));
+            }
+        }
+        IntervalSet { intervals: res }
+    }
+}
+
+/// A function on the integers that adds a constant offset to every value inside each of its
+/// pieces and leaves values outside all pieces unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PiecewiseShift {
+    /// sorted, disjoint (source interval, offset) pairs
+    pieces: Vec<(Interval, i64)>,
+}
+
+impl PiecewiseShift {
+    pub fn new() -> Self {
+        PiecewiseShift::default()
+    }
+
+    /// Shifts the values in `source` by `offset`. Values already covered by an earlier piece
+    /// keep the earlier shift.
+    pub fn add_piece(&mut self, source: Interval, offset: i64) {
+        let covered = IntervalSet::from_intervals(self.pieces.iter().map(|(x, _)| *x));
+        let uncovered = IntervalSet::from_intervals([source]).difference(&covered);
+        self.pieces
+            .extend(uncovered.iter().map(|interval| (*interval, offset)));
+        self.pieces.sort_unstable();
+    }
+
+    pub fn pieces(&self) -> &[(Interval, i64)] {
+        &self.pieces
+    }
+
+    pub fn apply(&self, value: i64) -> i64 {
+        let index = self.pieces.partition_point(|(x, _)| x.end <= value);
+        match self.pieces.get(index) {
+            Some((interval, offset)) if interval.contains(value) => value + offset,
+            _ => value,
+        }
+    }
+
+    fn sources(&self) -> IntervalSet {
+        IntervalSet::from_intervals(self.pieces.iter().map(|(x, _)| *x))
+    }
+
+    /// The image of every value in `set`.
+    pub fn apply_set(&self, set: &IntervalSet) -> IntervalSet {
+        let mut image: Vec<Interval> = set.difference(&self.sources()).intervals;
+        for (source, offset) in self.pieces.iter() {
+            let covered = set.intersection(&IntervalSet::from_intervals([*source]));
+            image.extend(covered.iter().map(|x| x.shifted(*offset)));
+        }
+        IntervalSet::from_intervals(image)
+    }
+
+    /// Every value that gets mapped into `set`.
+    pub fn preimage(&self, set: &IntervalSet) -> IntervalSet {
+        let mut preimage: Vec<Interval> = set.difference(&self.sources()).intervals;
+        for (source, offset) in self.pieces.iter() {
+            let shifted_back = IntervalSet::from_intervals(set.iter().map(|x| x.shifted(-offset)));
+            let covered = shifted_back.intersection(&IntervalSet::from_intervals([*source]));
+            preimage.extend(covered.intervals);
+        }
+        IntervalSet::from_intervals(preimage)
+    }
+
+    /// The map that applies `self` and then `next`. Pieces that end up not moving anything
+    /// are dropped, and neighbouring pieces with the same offset are merged.
+    pub fn then(&self, next: &PiecewiseShift) -> PiecewiseShift {
+        let mut pieces: Vec<(Interval, i64)> = Vec::new();
+        for (source, offset) in self.pieces.iter() {
+            let image = IntervalSet::from_intervals([source.shifted(*offset)]);
+            for (next_source, next_offset) in next.pieces.iter() {
+                let overlap = image.intersection(&IntervalSet::from_intervals([*next_source]));
+                pieces.extend(
+                    overlap
+                        .iter()
+                        .map(|x| (x.shifted(-offset), offset + next_offset)),
+                );
+            }
+            let untouched = image.difference(&next.sources());
+            pieces.extend(untouched.iter().map(|x| (x.shifted(-offset), *offset)));
+        }
+        // values `self` leaves alone only get moved by `next`
+        let passed_through = next.sources().difference(&self.sources());
+        for (next_source, next_offset) in next.pieces.iter() {
+            let covered = passed_through.intersection(&IntervalSet::from_intervals([*next_source]));
+            pieces.extend(covered.iter().map(|x| (*x, *next_offset)));
+        }
+
+        pieces.retain(|(_, offset)| *offset != 0);
+        pieces.sort_unstable();
+        let mut merged: Vec<(Interval, i64)> = Vec::with_capacity(pieces.len());
+        for (interval, offset) in pieces {
+            match merged.last_mut() {
+                Some((last, last_offset))
+                    if last.end == interval.start && *last_offset == offset =>
+                {
+                    last.end = interval.end
+                }
+                _ => merged.push((interval, offset)),
+            }
+        }
+        PiecewiseShift { pieces: merged }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Interval, IntervalSet, PiecewiseShift};
+    use crate::random::Xorshift;
+
+    const DOMAIN: std::ops::Range<i64> = -40..40;
+
+    fn random_interval(random: &mut Xorshift) -> Interval {
+        let start = random.below(50) as i64 - 25;
+        Interval::from_len(start, random.below(12) as i64)
+    }
+
+    fn random_set(random: &mut Xorshift) -> IntervalSet {
+        let count = random.below(5);
+        IntervalSet::from_intervals((0..count).map(|_| random_interval(random)))
+    }
+
+    fn brute_force(set: &IntervalSet) -> BTreeSet<i64> {
+        DOMAIN.filter(|&x| set.contains(x)).collect()
+    }
+
+    fn is_normalized(set: &IntervalSet) -> bool {
+        set.intervals().iter().all(|x| !x.is_empty())
+            && set.intervals().windows(2).all(|x| x[0].end < x[1].start)
+    }
+
+    #[test]
+    fn set_operations_match_brute_force() {
+        let mut random = Xorshift::new(0x9E3779B97F4A7C15);
+        for _ in 0..2000 {
+            let (a, b) = (random_set(&mut random), random_set(&mut random));
+            let (brute_a, brute_b) = (brute_force(&a), brute_force(&b));
+            assert_eq!(a.len() as usize, brute_a.len());
+
+            let union = a.union(&b);
+            let intersection = a.intersection(&b);
+            let difference = a.difference(&b);
+            for set in [&union, &intersection, &difference] {
+                assert!(is_normalized(set), "{:?}", set);
+            }
+            assert_eq!(brute_force(&union), &brute_a | &brute_b);
+            assert_eq!(brute_force(&intersection), &brute_a & &brute_b);
+            assert_eq!(brute_force(&difference), &brute_a - &brute_b);
+        }
+    }
+
+    #[test]
+    fn shift_matches_brute_force() {
+        let mut random = Xorshift::new(0xD1B54A32D192ED03);
+        for _ in 0..2000 {
+            let shift = random_shift(&mut random);
+            let set = random_set(&mut random);
+            let expected: BTreeSet<i64> = brute_force(&set)
+                .into_iter()
+                .map(|x| shift.apply(x))
+                .collect();
+            let image = shift.apply_set(&set);
+            assert!(is_normalized(&image));
+            let actual: BTreeSet<i64> = image.iter().flat_map(|x| x.start..x.end).collect();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    fn random_shift(random: &mut Xorshift) -> PiecewiseShift {
+        let mut shift = PiecewiseShift::new();
+        for _ in 0..random.below(4) {
+            shift.add_piece(random_interval(random), random.below(21) as i64 - 10);
+        }
+        shift
+    }
+
+    #[test]
+    fn composition_matches_brute_force() {
+        let mut random = Xorshift::new(0xA0761D6478BD642F);
+        for _ in 0..2000 {
+            let (first, second) = (random_shift(&mut random), random_shift(&mut random));
+            let composed = first.then(&second);
+            for x in DOMAIN {
+                assert_eq!(composed.apply(x), second.apply(first.apply(x)));
+            }
+        }
+    }
+
+    #[test]
+    fn preimage_matches_brute_force() {
+        let mut random = Xorshift::new(0xE7037ED1A0B428DB);
+        for _ in 0..2000 {
+            let shift = random_shift(&mut random);
+            let target = random_set(&mut random);
+            let preimage = shift.preimage(&target);
+            for x in DOMAIN {
+                assert_eq!(preimage.contains(x), target.contains(shift.apply(x)));
+            }
+        }
+    }
+
+    #[test]
+    fn earlier_pieces_take_priority() {
+        let mut shift = PiecewiseShift::new();
+        shift.add_piece(Interval::new(0, 10), 100);
+        shift.add_piece(Interval::new(5, 15), 1000);
+        assert_eq!(shift.apply(7), 107);
+        assert_eq!(shift.apply(12), 1012);
+        assert_eq!(shift.apply(15), 15);
+        assert_eq!(shift.pieces().len(), 2);
+    }
+}
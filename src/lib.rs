@@ -1 +1,6 @@
+pub mod cycles;
+pub mod intervals;
+pub mod parallel;
+pub mod random;
+pub mod sequence;
 pub mod utils;
@@ -0,0 +1,73 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+/// Applies `f` to every item, returning the results in the same order as `items`.
+///
+/// With the `parallel` feature on, the items are shared out between one thread per core;
+/// otherwise they're done one after another on the current thread. Either way the results
+/// are the same, so `f` shouldn't care what order it's called in.
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    if cfg!(feature = "parallel") {
+        let threads = thread::available_parallelism().map_or(1, |x| x.get());
+        map_on_threads(items, f, threads)
+    } else {
+        items.iter().map(f).collect()
+    }
+}
+
+fn map_on_threads<T: Sync, R: Send>(
+    items: &[T],
+    f: impl Fn(&T) -> R + Sync,
+    threads: usize,
+) -> Vec<R> {
+    // threads take the next item whenever they're free, so a few slow items don't hold up
+    // everything that happened to be given to the same thread
+    let next_item = AtomicUsize::new(0);
+    let finished: Vec<Vec<(usize, R)>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next_item.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        workers.into_iter().map(|x| x.join().unwrap()).collect()
+    });
+
+    let mut results: Vec<Option<R>> = (0..items.len()).map(|_| None).collect();
+    for (i, result) in finished.into_iter().flatten() {
+        results[i] = Some(result);
+    }
+    results.into_iter().map(|x| x.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{map, map_on_threads};
+
+    #[test]
+    fn keeps_order() {
+        let items: Vec<u64> = (0..1000).collect();
+        let expected: Vec<u64> = items.iter().map(|x| x * x % 97).collect();
+        assert_eq!(map(&items, |x| x * x % 97), expected);
+        for threads in [1, 2, 3, 8, 2000] {
+            assert_eq!(map_on_threads(&items, |x| x * x % 97, threads), expected);
+        }
+    }
+
+    #[test]
+    fn no_items() {
+        let items: Vec<u64> = Vec::new();
+        assert_eq!(map(&items, |x| x + 1), Vec::<u64>::new());
+        assert_eq!(map_on_threads(&items, |x| x + 1, 4), Vec::<u64>::new());
+    }
+}
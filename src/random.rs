@@ -0,0 +1,52 @@
+/// A xorshift64 generator, for repeatable made-up data (property tests, benchmark input)
+/// without an extra dependency. The same seed always gives the same numbers.
+#[derive(Debug, Clone)]
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    /// # Panics
+    ///
+    /// Panics if `seed` is zero, since xorshift never gets away from zero.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift needs a nonzero seed");
+        Xorshift(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..bound`. Slightly favours small numbers unless `bound` divides 2^64,
+    /// which doesn't matter for what this is used for.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Xorshift;
+
+    #[test]
+    fn repeatable() {
+        let mut a = Xorshift::new(0x9E3779B97F4A7C15);
+        let mut b = a.clone();
+        let first: Vec<u64> = (0..100).map(|_| a.next_u64()).collect();
+        assert!(first.iter().all(|&x| x != 0));
+        assert_eq!(first, (0..100).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first[0], Xorshift::new(1).next_u64());
+    }
+
+    #[test]
+    fn below_bound() {
+        let mut random = Xorshift::new(7);
+        let mut seen = [false; 10];
+        for _ in 0..1000 {
+            seen[random.below(10) as usize] = true;
+        }
+        assert!(seen.iter().all(|&x| x));
+    }
+}
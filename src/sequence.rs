@@ -0,0 +1,280 @@
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Returned when a value no longer fits in the integer types used here.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "arithmetic overflow")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// An exact fraction, always kept in lowest terms with a positive denominator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    /// # Panics
+    ///
+    /// Panics if `denominator` is zero.
+    pub fn new(numerator: i128, denominator: i128) -> Result<Self, Overflow> {
+        assert_ne!(denominator, 0, "zero denominator");
+        let divisor = gcd(numerator, denominator);
+        let sign = denominator.signum();
+        Ok(Rational {
+            numerator: (numerator / divisor).checked_mul(sign).ok_or(Overflow)?,
+            denominator: (denominator / divisor).checked_mul(sign).ok_or(Overflow)?,
+        })
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denominator == 1
+    }
+
+    pub fn checked_add(self, other: Rational) -> Result<Self, Overflow> {
+        let numerator = self
+            .numerator
+            .checked_mul(other.denominator)
+            .zip(other.numerator.checked_mul(self.denominator))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or(Overflow)?;
+        let denominator = self
+            .denominator
+            .checked_mul(other.denominator)
+            .ok_or(Overflow)?;
+        Rational::new(numerator, denominator)
+    }
+
+    pub fn checked_sub(self, other: Rational) -> Result<Self, Overflow> {
+        let negated = Rational {
+            numerator: other.numerator.checked_neg().ok_or(Overflow)?,
+            denominator: other.denominator,
+        };
+        self.checked_add(negated)
+    }
+
+    pub fn checked_mul(self, other: Rational) -> Result<Self, Overflow> {
+        // cancel across first so the products stay as small as possible
+        let a = gcd(self.numerator, other.denominator).max(1);
+        let b = gcd(other.numerator, self.denominator).max(1);
+        let numerator = (self.numerator / a)
+            .checked_mul(other.numerator / b)
+            .ok_or(Overflow)?;
+        let denominator = (self.denominator / b)
+            .checked_mul(other.denominator / a)
+            .ok_or(Overflow)?;
+        Rational::new(numerator, denominator)
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Rational {
+            numerator: value as i128,
+            denominator: 1,
+        }
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+/// Parses either an integer like `-3` or a fraction like `7/2`.
+impl FromStr for Rational {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |x: &str| {
+            x.trim()
+                .parse::<i128>()
+                .map_err(|e| format!("{}: {}", x, e))
+        };
+        match s.split_once('/') {
+            Some((numerator, denominator)) => {
+                let denominator = parse(denominator)?;
+                if denominator == 0 {
+                    return Err(format!("{}: zero denominator", s));
+                }
+                Rational::new(parse(numerator)?, denominator).map_err(|e| e.to_string())
+            }
+            None => Ok(Rational {
+                numerator: parse(s)?,
+                denominator: 1,
+            }),
+        }
+    }
+}
+
+/// The lowest degree polynomial through the values of a sequence, where the sequence's first
+/// value is at `x = 0`, the second at `x = 1` and so on.
+///
+/// It's stored in Newton's forward difference form, `p(x) = sum of d_k * C(x, k)`, where `d_k`
+/// is the first entry of the `k`th row of the difference table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    differences: Vec<i128>,
+}
+
+impl Polynomial {
+    pub fn fit(values: &[i64]) -> Result<Self, Overflow> {
+        let mut row: Vec<i128> = values.iter().map(|&x| x as i128).collect();
+        let mut differences = Vec::with_capacity(row.len());
+        while !row.is_empty() && row.iter().any(|&x| x != 0) {
+            differences.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|x| x[1].checked_sub(x[0]).ok_or(Overflow))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(Polynomial { differences })
+    }
+
+    /// `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.differences.len().checked_sub(1)
+    }
+
+    /// The value at `x`, which is always a whole number since the polynomial is whole at
+    /// every integer it was fitted to.
+    pub fn at(&self, x: i64) -> Result<i64, Overflow> {
+        let value = self.at_rational(Rational::from(x))?;
+        debug_assert!(value.is_integer());
+        i64::try_from(value.numerator()).map_err(|_| Overflow)
+    }
+
+    /// The exact value at any rational `x`, such as halfway between two terms.
+    pub fn at_rational(&self, x: Rational) -> Result<Rational, Overflow> {
+        let mut total = Rational::from(0);
+        // C(x, k) built up one factor at a time as C(x, k - 1) * (x - k + 1) / k
+        let mut binomial = Rational::from(1);
+        for (k, &difference) in self.differences.iter().enumerate() {
+            if k > 0 {
+                let factor = x.checked_sub(Rational::from(k as i64 - 1))?;
+                binomial = binomial
+                    .checked_mul(factor)?
+                    .checked_mul(Rational::new(1, k as i128)?)?;
+            }
+            let term = binomial.checked_mul(Rational::new(difference, 1)?)?;
+            total = total.checked_add(term)?;
+        }
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Overflow, Polynomial, Rational};
+    use crate::random::Xorshift;
+
+    fn evaluate(coefficients: &[i64], x: i64) -> i64 {
+        coefficients.iter().rev().fold(0, |total, c| total * x + c)
+    }
+
+    #[test]
+    fn degrees() {
+        assert_eq!(Polynomial::fit(&[]).unwrap().degree(), None);
+        assert_eq!(Polynomial::fit(&[0, 0, 0]).unwrap().degree(), None);
+        assert_eq!(Polynomial::fit(&[5, 5, 5, 5]).unwrap().degree(), Some(0));
+        assert_eq!(
+            Polynomial::fit(&[0, 3, 6, 9, 12]).unwrap().degree(),
+            Some(1)
+        );
+        assert_eq!(
+            Polynomial::fit(&[1, 3, 6, 10, 15]).unwrap().degree(),
+            Some(2)
+        );
+        // two points of anything fit a line
+        assert_eq!(Polynomial::fit(&[4, 7]).unwrap().degree(), Some(1));
+        // the last row of differences is nonzero, so nothing lower fits
+        assert_eq!(Polynomial::fit(&[0, 0, 0, 1]).unwrap().degree(), Some(3));
+    }
+
+    #[test]
+    fn matches_random_polynomials() {
+        let mut random = Xorshift::new(0x9E3779B97F4A7C15);
+        for _ in 0..500 {
+            let degree = random.below(6) as usize;
+            let mut coefficients: Vec<i64> =
+                (0..=degree).map(|_| random.below(21) as i64 - 10).collect();
+            if coefficients[degree] == 0 {
+                coefficients[degree] = 1;
+            }
+            let extra_terms = random.below(4) as i64;
+            let values: Vec<i64> = (0..=degree as i64 + extra_terms)
+                .map(|x| evaluate(&coefficients, x))
+                .collect();
+            let polynomial = Polynomial::fit(&values).unwrap();
+            assert_eq!(polynomial.degree(), Some(degree), "{:?}", coefficients);
+            for x in -30..30 {
+                assert_eq!(polynomial.at(x), Ok(evaluate(&coefficients, x)));
+            }
+        }
+    }
+
+    #[test]
+    fn rational_offsets() {
+        // x^2 / 2 + x / 2, the triangular numbers
+        let polynomial = Polynomial::fit(&[0, 1, 3, 6]).unwrap();
+        let half: Rational = "1/2".parse().unwrap();
+        assert_eq!(
+            polynomial.at_rational(half),
+            Ok(Rational::new(3, 8).unwrap())
+        );
+        let value = polynomial.at_rational("-7/3".parse().unwrap()).unwrap();
+        assert_eq!(value.to_string(), "14/9");
+        assert_eq!(
+            polynomial.at_rational(Rational::from(4)),
+            Ok(Rational::from(10))
+        );
+    }
+
+    #[test]
+    fn parse_rational() {
+        assert_eq!("6/-4".parse::<Rational>().unwrap().to_string(), "-3/2");
+        assert_eq!("12".parse::<Rational>(), Ok(Rational::from(12)));
+        assert!("1/0".parse::<Rational>().is_err());
+        assert!("x".parse::<Rational>().is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        let polynomial = Polynomial::fit(&[0, i64::MAX]).unwrap();
+        assert_eq!(polynomial.at(1), Ok(i64::MAX));
+        assert_eq!(polynomial.at(2), Err(Overflow));
+        assert_eq!(polynomial.at(-1), Ok(-i64::MAX));
+        let steep = Polynomial::fit(&[0, i64::MAX, i64::MIN, i64::MAX]).unwrap();
+        assert_eq!(steep.at(1_000_000_000), Err(Overflow));
+    }
+}